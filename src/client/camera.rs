@@ -1,47 +1,322 @@
 use engine::{
   application::{
-    components::{CameraComponent, LightComponent, SelfComponent},
+    components::{CameraComponent, LightComponent, PhysicsComponent, SelfComponent},
     scene::{IdComponent, Scene, TransformComponent},
   },
-  systems::{rendering::CameraConfig, Backpack, Initializable, Inventory, System},
-  utils::units::Radians,
+  systems::{
+    input::InputsReader, physics::PhysicsController, rendering::CameraConfig, Backpack,
+    Initializable, Inventory, System,
+  },
+  utils::units::{Radians, Time},
+};
+use nalgebra::{Isometry3, Point3, Rotation3, Unit, Vector3};
+use rapier3d::prelude::*;
+use std::f32::consts::PI;
+
+use crate::shared::input::{Actions, PlayerInput};
+
+use crate::shared::components::{
+  BoardState, CameraEffectsComponent, ChaseCameraComponent, PlayerMovementComponent, Shake,
 };
-use nalgebra::{Isometry3, Point3, Unit, Vector3};
 
-pub struct CameraSystem {}
+pub struct CameraSystem {
+  inputs: InputsReader<PlayerInput>,
+  physics_controller: PhysicsController,
+  position: Option<Vector3<f32>>,
+  front: Unit<Vector3<f32>>,
+  running_time: f32,
+  fov_widening: f32,
+  last_state: BoardState,
+  last_speed: f32,
+  last_boost: f32,
+  shake: Option<Shake>,
+  shake_time_left: f32,
+  yaw: f32,
+  pitch: f32,
+  free_look_idle_time: f32,
+}
 
 impl Initializable for CameraSystem {
-  fn initialize(_: &Inventory) -> Self {
-    Self {}
+  fn initialize(inventory: &Inventory) -> Self {
+    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
+    let physics_controller = inventory.get::<PhysicsController>().clone();
+
+    Self {
+      inputs,
+      physics_controller,
+      position: None,
+      front: Unit::new_normalize(Vector3::z()),
+      running_time: 0.0,
+      fov_widening: 0.0,
+      last_state: BoardState::Idle,
+      last_speed: 0.0,
+      last_boost: 1.0,
+      shake: None,
+      shake_time_left: 0.0,
+      yaw: 0.0,
+      pitch: 0.0,
+      free_look_idle_time: 0.0,
+    }
   }
 }
 
 impl System for CameraSystem {
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
-    for (_, (id, transform, camera, _)) in &mut scene.query::<(
-      &IdComponent,
-      &TransformComponent,
-      &CameraComponent,
-      &SelfComponent,
-    )>() {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    self.running_time += delta_time;
+
+    let input = self.inputs.read();
+
+    for (_, (id, transform, camera, chase_camera, effects, player_component, physics, _)) in
+      &mut scene.query::<(
+        &IdComponent,
+        &TransformComponent,
+        &CameraComponent,
+        Option<&ChaseCameraComponent>,
+        Option<&CameraEffectsComponent>,
+        Option<&PlayerMovementComponent>,
+        Option<&PhysicsComponent>,
+        &SelfComponent,
+      )>()
+    {
+      let chase_camera = chase_camera.copied().unwrap_or_default();
       let eye_direction = transform.get_euler_direction();
 
-      let offset = (eye_direction.into_inner() * -5.) + Vector3::new(0.0, 0.75, 0.0);
-      let character_position = Point3::from(transform.translation + Vector3::new(0.0, 0.05, 0.0));
-      let camera_position = Point3::from(transform.translation + offset);
-      let isometry = Isometry3::look_at_rh(&camera_position, &character_position, &Vector3::y());
+      self.update_free_look(&input, &chase_camera, delta_time);
+      let offset = self.arm(&input, eye_direction, &chase_camera);
+      let character_position =
+        transform.translation + Vector3::new(0.0, chase_camera.look_height, 0.0);
+      let desired_position =
+        character_position + self.pull_in(character_position, offset, physics, &chase_camera);
+
+      let position = match self.position {
+        Some(position) => position.lerp(
+          &desired_position,
+          damping_factor(chase_camera.position_damping, delta_time),
+        ),
+        None => desired_position,
+      };
+      self.position = Some(position);
+
+      if let Some(look_direction) = Unit::try_new(character_position - position, f32::EPSILON) {
+        self.front = self
+          .front
+          .try_slerp(
+            &look_direction,
+            damping_factor(chase_camera.rotation_damping, delta_time),
+            f32::EPSILON,
+          )
+          .unwrap_or(look_direction);
+      }
+
+      let isometry = Isometry3::look_at_rh(
+        &Point3::from(position),
+        &Point3::from(character_position),
+        &Vector3::y(),
+      );
       let view = isometry.to_homogeneous();
 
+      let mut shake_offset = Vector3::zeros();
+      if let Some(effects) = effects
+        && let Some(player_component) = player_component
+      {
+        self.update_fov(effects, player_component, delta_time);
+        self.trigger_shakes(effects, player_component);
+        shake_offset = self.shake_offset(effects, delta_time);
+      }
+
       if let CameraComponent::Perspective { fovy, zfar, znear, .. } = camera
         && let Some(camera) = backpack.get_mut::<CameraConfig>()
       {
-        camera.fovy = *fovy;
+        camera.fovy = Radians::new(**fovy + self.fov_widening);
         camera.znear = *znear;
         camera.zfar = *zfar;
-        camera.translation = transform.translation + offset;
-        camera.front = eye_direction;
+        camera.translation = position + shake_offset;
+        camera.front = self.front;
         camera.up = Unit::new_normalize(Vector3::y());
       }
     }
   }
 }
+
+impl CameraSystem {
+  /// Orbits the arm around the board while the mouse is locked, and swings it
+  /// back behind the board after `recenter_delay` seconds without input.
+  fn update_free_look(
+    &mut self,
+    input: &PlayerInput,
+    chase_camera: &ChaseCameraComponent,
+    delta_time: f32,
+  ) {
+    if input.mouse_lock && input.mouse_delta.norm() > f32::EPSILON {
+      self.yaw -= input.mouse_delta.x * chase_camera.free_look_sensitivity;
+      self.pitch = (self.pitch - input.mouse_delta.y * chase_camera.free_look_sensitivity)
+        .clamp(-chase_camera.max_pitch, chase_camera.max_pitch);
+      self.free_look_idle_time = 0.0;
+    } else {
+      self.free_look_idle_time += delta_time;
+    }
+
+    // keep the yaw in [-PI, PI] so recentering takes the short way around
+    if self.yaw > PI {
+      self.yaw -= 2.0 * PI;
+    } else if self.yaw < -PI {
+      self.yaw += 2.0 * PI;
+    }
+
+    if self.free_look_idle_time >= chase_camera.recenter_delay {
+      let recenter = 1.0 - damping_factor(chase_camera.recenter_speed, delta_time);
+      self.yaw *= recenter;
+      self.pitch *= recenter;
+    }
+  }
+
+  /// Offset from the board to the end of the spring arm, with free-look and
+  /// look-behind applied.
+  fn arm(
+    &self,
+    input: &PlayerInput,
+    eye_direction: Unit<Vector3<f32>>,
+    chase_camera: &ChaseCameraComponent,
+  ) -> Vector3<f32> {
+    let mut yaw = self.yaw;
+    if input.actions.contains(&Actions::LookBehind) {
+      yaw += PI;
+    }
+
+    let behind = Rotation3::from_axis_angle(&Vector3::y_axis(), yaw) * -eye_direction.into_inner();
+    let arm = match Unit::try_new(behind.cross(&Vector3::y()), f32::EPSILON) {
+      Some(right) => Rotation3::from_axis_angle(&right, self.pitch) * behind,
+      None => behind,
+    };
+
+    arm * chase_camera.arm_length + Vector3::new(0.0, chase_camera.height, 0.0)
+  }
+
+  /// Shortens the spring arm so the camera stays in front of any collider
+  /// between it and the board.
+  fn pull_in(
+    &self,
+    character_position: Vector3<f32>,
+    offset: Vector3<f32>,
+    physics: Option<&PhysicsComponent>,
+    chase_camera: &ChaseCameraComponent,
+  ) -> Vector3<f32> {
+    let arm_length = offset.norm();
+    let Some(direction) = Unit::try_new(offset, f32::EPSILON) else {
+      return offset;
+    };
+
+    let ray = Ray::new(character_position.into(), direction.into_inner());
+    let solid = true;
+    let mut filter = QueryFilter::default();
+    if let Some(physics) = physics
+      && let Some(rigidbody_handle) = self
+        .physics_controller
+        .get_rigid_body(&physics.joint.body.id)
+    {
+      filter = filter.exclude_rigid_body(rigidbody_handle);
+    }
+
+    match self
+      .physics_controller
+      .raycast(&ray, arm_length, solid, filter)
+    {
+      Some((_, _, intersection)) => {
+        direction.into_inner() * (intersection.toi - chase_camera.collision_margin).max(0.0)
+      }
+      None => offset,
+    }
+  }
+
+  /// Eases the field of view wider as the board gets closer to its top speed.
+  fn update_fov(
+    &mut self,
+    effects: &CameraEffectsComponent,
+    player_component: &PlayerMovementComponent,
+    delta_time: f32,
+  ) {
+    let speed_ratio = if player_component.max_velocity > 0.0 {
+      player_component.current_velocity.abs() / player_component.max_velocity
+    } else {
+      0.0
+    };
+    let range = (1.0 - effects.fov_start_speed).max(f32::EPSILON);
+    let t = (speed_ratio - effects.fov_start_speed) / range;
+    let target = effects.fov_widening * effects.fov_easing.apply(t);
+
+    self.fov_widening +=
+      (target - self.fov_widening) * damping_factor(effects.fov_damping, delta_time);
+  }
+
+  /// Starts a shake when the board enters a state that has one set up, or when
+  /// it loses enough speed in one frame to count as an impact.
+  fn trigger_shakes(
+    &mut self,
+    effects: &CameraEffectsComponent,
+    player_component: &PlayerMovementComponent,
+  ) {
+    let speed = player_component.current_velocity.abs();
+
+    if player_component.state != self.last_state
+      && let Some(state_shake) = effects
+        .state_shakes
+        .iter()
+        .find(|state_shake| state_shake.state == player_component.state)
+    {
+      self.start_shake(state_shake.shake);
+    }
+
+    if self.last_speed - speed > effects.impact_speed_loss * player_component.max_velocity {
+      self.start_shake(effects.impact_shake);
+    }
+
+    // a boost pad or the boost meter kicking in, or a pad topping a boost up
+    let boost = player_component.boost_multiplier * player_component.meter_speed_multiplier;
+    if boost > self.last_boost + 0.01 {
+      self.start_shake(effects.boost_shake);
+    }
+
+    self.last_state = player_component.state;
+    self.last_speed = speed;
+    self.last_boost = boost;
+  }
+
+  fn start_shake(&mut self, shake: Shake) {
+    // a weaker shake never cuts a stronger one short
+    if let Some(current) = self.shake
+      && current.intensity * self.shake_time_left / current.duration > shake.intensity
+    {
+      return;
+    }
+    self.shake = Some(shake);
+    self.shake_time_left = shake.duration;
+  }
+
+  fn shake_offset(&mut self, effects: &CameraEffectsComponent, delta_time: f32) -> Vector3<f32> {
+    let Some(shake) = self.shake else {
+      return Vector3::zeros();
+    };
+
+    self.shake_time_left -= delta_time;
+    if self.shake_time_left <= 0.0 || shake.duration <= 0.0 {
+      self.shake = None;
+      return Vector3::zeros();
+    }
+
+    let falloff = self.shake_time_left / shake.duration;
+    let amplitude = shake.intensity * falloff * falloff;
+    let phase = self.running_time * effects.shake_frequency;
+
+    Vector3::new(
+      f32::sin(phase),
+      f32::sin(phase * 1.3 + 1.7),
+      f32::sin(phase * 0.7 + 3.1),
+    ) * amplitude
+  }
+}
+
+/// Frame-rate independent interpolation factor for exponential damping.
+fn damping_factor(damping: f32, delta_time: f32) -> f32 {
+  1.0 - f32::exp(-damping * delta_time)
+}
@@ -0,0 +1,53 @@
+use engine::application::{components::ModelComponent, scene::Scene};
+use engine::systems::{Backpack, Initializable, Inventory, System};
+use engine::Entity;
+use std::collections::HashSet;
+
+use crate::shared::components::GhostComponent;
+use crate::shared::prefabs::prefab_component;
+
+/// Draws ghosts with the translucent model of their `Ghost` look prefab
+/// instead of the opaque one they were spawned with, so they can't be mistaken
+/// for real racers.
+pub struct GhostLookSystem {
+  dressed: HashSet<Entity>,
+}
+
+impl Initializable for GhostLookSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {
+      dressed: HashSet::new(),
+    }
+  }
+}
+
+impl System for GhostLookSystem {
+  fn run(&mut self, scene: &mut Scene, _: &mut Backpack) {
+    let ghosts: Vec<(Entity, String)> = scene
+      .query_mut::<&GhostComponent>()
+      .into_iter()
+      .map(|(entity, ghost)| (entity, ghost.look.clone()))
+      .collect();
+    self
+      .dressed
+      .retain(|entity| ghosts.iter().any(|(ghost, _)| ghost == entity));
+
+    for (entity, look) in ghosts {
+      if look.is_empty() || self.dressed.contains(&entity) {
+        continue;
+      }
+      let Some(model) = scene
+        .iter_prefabs()
+        .find(|(_, prefab)| prefab.tag.name == look)
+        .and_then(|(_, prefab)| prefab_component::<ModelComponent>(prefab).cloned())
+      else {
+        continue;
+      };
+
+      if let Ok(ghost_model) = scene.query_one_mut::<&mut ModelComponent>(entity) {
+        *ghost_model = model;
+        self.dressed.insert(entity);
+      }
+    }
+  }
+}
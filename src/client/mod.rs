@@ -1,4 +1,7 @@
 mod camera;
+mod ghosts;
+pub mod replay;
+mod spectator_camera;
 
 use engine::{
   application::{
@@ -13,7 +16,10 @@ use engine::{
 };
 
 use crate::shared::input::PlayerInput;
+use crate::shared::loadout::{self, Loadout};
 
+use crate::shared::systems::animation_controller::AnimationControllerSystem;
+use crate::shared::systems::expiry::ExpirySystem;
 use crate::shared::systems::player_movement::PlayerMovementSystem;
 
 // 4k
@@ -42,9 +48,18 @@ pub async fn main(
   access_token: String,
   udp_url: String,
   tcp_url: String,
+  board: Option<String>,
+  avatar: Option<String>,
+  spectate: bool,
 ) {
   wasm_logger::init(wasm_logger::Config::default());
   grow_memory(GROW_MEMORY_IN_MB);
+  loadout::set_spectating(spectate);
+  loadout::request_join(Loadout {
+    board,
+    avatar,
+    spectate,
+  });
   let mut runner = Scheduler::new(FRAMES_PER_SECOND, canvas_id);
 
   log::debug!("assets location: {:?}", &assets_location);
@@ -66,6 +81,11 @@ pub async fn main(
 
   runner.attach_plugin(hdr);
   runner.attach_system::<camera::CameraSystem>();
+  runner.attach_system::<replay::ReplayPlaybackSystem>();
+  runner.attach_system::<spectator_camera::SpectatorCameraSystem>();
+  runner.attach_system::<ghosts::GhostLookSystem>();
   runner.attach_system::<PlayerMovementSystem>();
+  runner.attach_system::<AnimationControllerSystem>();
+  runner.attach_system::<ExpirySystem>();
   runner.run().await;
 }
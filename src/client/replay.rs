@@ -0,0 +1,218 @@
+use engine::{
+  application::{
+    components::{AnimationComponent, ModelComponent, ParentComponent},
+    scene::{Prefab, PrefabId, Scene, TransformComponent},
+  },
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Time,
+  Entity,
+};
+use std::sync::Mutex;
+
+use crate::shared::components::{RacerComponent, Role};
+use crate::shared::prefabs::{pick, prefab_component};
+use crate::shared::replay::{Replay, ReplayEventKind, ReplayPlayback, ReplayRacer};
+
+/// What the page asked the replay player to do since the last frame.
+#[derive(Debug, Clone)]
+pub enum ReplayCommand {
+  Play(Replay),
+  Stop,
+  Pause(bool),
+  Seek(f32),
+  Speed(f32),
+}
+
+static COMMANDS: Mutex<Vec<ReplayCommand>> = Mutex::new(Vec::new());
+
+pub fn send(command: ReplayCommand) {
+  COMMANDS.lock().unwrap().push(command);
+}
+
+/// The stand-ins spawned for each racer of the replay being played, indexed
+/// like `Replay::racers`, and their boards. Empty when nothing is playing.
+/// Lives in the backpack so the spectator camera can follow them.
+#[derive(Debug, Default)]
+pub struct ReplayState {
+  pub entities: Vec<Entity>,
+  pub boards: Vec<Entity>,
+}
+
+impl ReplayState {
+  pub fn is_playing(&self) -> bool {
+    !self.entities.is_empty()
+  }
+}
+
+/// Plays a `Replay` back through the scene. Each racer gets a stand-in built
+/// from the level's prefabs with the loadout they raced with, which is moved
+/// along the recorded transforms.
+pub struct ReplayPlaybackSystem {
+  playback: Option<ReplayPlayback>,
+}
+
+impl Initializable for ReplayPlaybackSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self { playback: None }
+  }
+}
+
+impl System for ReplayPlaybackSystem {
+  fn attach(&mut self, _: &mut Scene, backpack: &mut Backpack) {
+    backpack.insert(ReplayState::default());
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+
+    let commands: Vec<ReplayCommand> = COMMANDS.lock().unwrap().drain(..).collect();
+    for command in commands {
+      match command {
+        ReplayCommand::Play(replay) => {
+          self.stop(scene, backpack);
+          self.start(scene, backpack, replay);
+        }
+        ReplayCommand::Stop => self.stop(scene, backpack),
+        ReplayCommand::Pause(paused) => {
+          if let Some(playback) = &mut self.playback {
+            playback.paused = paused;
+          }
+        }
+        ReplayCommand::Seek(time) => {
+          if let Some(playback) = &mut self.playback {
+            playback.seek(time);
+          }
+        }
+        ReplayCommand::Speed(speed) => {
+          if let Some(playback) = &mut self.playback {
+            playback.set_speed(speed);
+          }
+        }
+      }
+    }
+
+    let Some(playback) = &mut self.playback else {
+      return;
+    };
+    let Some(state) = backpack.get::<ReplayState>() else {
+      return;
+    };
+
+    for event in playback.advance(delta_time) {
+      let Some(entity) = state.entities.get(event.racer as usize) else {
+        continue;
+      };
+      if let ReplayEventKind::Finish { place } = event.kind
+        && let Ok(racer) = scene.query_one_mut::<&mut RacerComponent>(*entity)
+      {
+        racer.place = place;
+        racer.finished = true;
+      }
+    }
+
+    for (racer, translation, rotation) in playback.replay.sample(playback.time) {
+      let Some(entity) = state.entities.get(racer as usize) else {
+        continue;
+      };
+      if let Ok(transform) = scene.query_one_mut::<&mut TransformComponent>(*entity) {
+        transform.translation = translation;
+        transform.rotation = rotation;
+      }
+    }
+  }
+}
+
+impl ReplayPlaybackSystem {
+  fn start(&mut self, scene: &mut Scene, backpack: &mut Backpack, replay: Replay) {
+    let catalog: Vec<Prefab> = scene
+      .iter_prefabs()
+      .map(|(_, prefab)| prefab.clone())
+      .collect();
+    let Some(avatar) = pick(&catalog, Role::PlayerAvatar, None) else {
+      log::warn!(
+        "no player avatar prefab to play replay of {:?} with",
+        replay.level
+      );
+      return;
+    };
+
+    let mut entities = vec![];
+    let mut boards = vec![];
+    for racer in &replay.racers {
+      let (entity, board) = spawn_racer(scene, &catalog, avatar, racer);
+      entities.push(entity);
+      boards.extend(board);
+    }
+
+    log::info!(
+      "playing replay of {:?}, {:.1}s",
+      replay.level,
+      replay.duration()
+    );
+    if let Some(state) = backpack.get_mut::<ReplayState>() {
+      state.entities = entities;
+      state.boards = boards;
+    }
+    self.playback = Some(ReplayPlayback::new(replay));
+  }
+
+  fn stop(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    self.playback = None;
+
+    if let Some(state) = backpack.get_mut::<ReplayState>() {
+      for entity in state.entities.drain(..).chain(state.boards.drain(..)) {
+        let _ = scene.despawn(entity);
+      }
+    }
+  }
+}
+
+/// Builds a stand-in for a recorded racer from the level's avatar prefab,
+/// dressed with the character and board of the racer's loadout, keeping only
+/// what it takes to be drawn and followed.
+fn spawn_racer(
+  scene: &mut Scene,
+  catalog: &[Prefab],
+  avatar: &Prefab,
+  racer: &ReplayRacer,
+) -> (Entity, Option<Entity>) {
+  let mut prefab = avatar.clone();
+  prefab.components.retain(|component| {
+    let component = component.as_any();
+    component.is::<ModelComponent>()
+      || component.is::<AnimationComponent>()
+      || component.is::<ParentComponent>()
+      || component.is::<RacerComponent>()
+  });
+  let prefab_id = PrefabId::new();
+  *prefab.id = prefab_id.clone();
+
+  let entity = scene.create_raw_entity(&format!("Replay {}", racer.username));
+  scene.create_with_prefab(entity, prefab);
+
+  if let Some(character) = pick(catalog, Role::Character, racer.loadout.avatar.as_deref())
+    && let Ok((model, animation)) =
+      scene.query_one_mut::<(&mut ModelComponent, &mut AnimationComponent)>(entity)
+  {
+    if let Some(character_model) = prefab_component::<ModelComponent>(character) {
+      *model = character_model.clone();
+    }
+    if let Some(character_animation) = prefab_component::<AnimationComponent>(character) {
+      *animation = character_animation.clone();
+    }
+  }
+
+  let board = pick(catalog, Role::Board, racer.loadout.board.as_deref()).map(|board| {
+    let mut board = board.clone();
+    *board.id = PrefabId::new();
+
+    let board_entity = scene.create_raw_entity(&format!("Replay {} board", racer.username));
+    scene.create_with_prefab(board_entity, board);
+    if let Ok(parent) = scene.query_one_mut::<&mut ParentComponent>(board_entity) {
+      parent.id = prefab_id.clone();
+    }
+    board_entity
+  });
+
+  (entity, board)
+}
@@ -0,0 +1,225 @@
+use engine::{
+  application::{
+    components::{CameraComponent, SelfComponent},
+    scene::{Scene, TransformComponent},
+  },
+  systems::{
+    input::InputsReader, rendering::CameraConfig, Backpack, Initializable, Inventory, System,
+  },
+  utils::units::Time,
+  Entity,
+};
+use nalgebra::{Rotation3, Unit, Vector3};
+use std::collections::HashSet;
+
+use crate::client::replay::ReplayState;
+use crate::shared::components::{RacerComponent, SpectatorCameraComponent};
+use crate::shared::input::{Actions, PlayerInput};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpectatorMode {
+  /// Sits behind one racer, picked with next/previous.
+  Follow,
+  /// Flies around the track with the movement keys and the mouse.
+  FreeFly,
+  /// Sits behind whoever is in first place.
+  Leader,
+}
+
+impl SpectatorMode {
+  fn next(self) -> Self {
+    match self {
+      Self::Follow => Self::FreeFly,
+      Self::FreeFly => Self::Leader,
+      Self::Leader => Self::Follow,
+    }
+  }
+}
+
+/// Drives the camera for clients that have no player of their own, because
+/// they joined as spectators, the race had already started or they finished
+/// it, and for anyone watching a replay.
+pub struct SpectatorCameraSystem {
+  inputs: InputsReader<PlayerInput>,
+  mode: SpectatorMode,
+  followed: Option<Entity>,
+  position: Option<Vector3<f32>>,
+  yaw: f32,
+  pitch: f32,
+  held: HashSet<Actions>,
+}
+
+impl Initializable for SpectatorCameraSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
+
+    Self {
+      inputs,
+      mode: SpectatorMode::Leader,
+      followed: None,
+      position: None,
+      yaw: 0.0,
+      pitch: 0.0,
+      held: HashSet::new(),
+    }
+  }
+}
+
+impl System for SpectatorCameraSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let replay_entities = backpack
+      .get::<ReplayState>()
+      .map(|state| state.entities.clone())
+      .unwrap_or_default();
+
+    // racing clients are looked after by the chase camera, unless they are
+    // watching a replay
+    if replay_entities.is_empty()
+      && scene
+        .query_mut::<(&CameraComponent, &SelfComponent)>()
+        .into_iter()
+        .next()
+        .is_some()
+    {
+      self.position = None;
+      return;
+    }
+
+    let delta_time = **backpack.get::<Time>().unwrap();
+    let input = self.inputs.read();
+    let pressed = self.pressed(&input);
+
+    let settings = scene
+      .query_mut::<&SpectatorCameraComponent>()
+      .into_iter()
+      .next()
+      .map(|(_, settings)| *settings)
+      .unwrap_or_default();
+
+    let mut racers: Vec<(Entity, TransformComponent, RacerComponent)> = scene
+      .query_mut::<(&TransformComponent, &RacerComponent)>()
+      .into_iter()
+      .filter(|(entity, _)| replay_entities.is_empty() || replay_entities.contains(entity))
+      .map(|(entity, (transform, racer))| (entity, transform.clone(), *racer))
+      .collect();
+    racers.sort_by_key(|(entity, _, _)| *entity);
+
+    if pressed.contains(&Actions::SpectateMode) {
+      self.mode = self.mode.next();
+      log::info!("spectating in {:?} mode", self.mode);
+    }
+    if pressed.contains(&Actions::SpectateNext) {
+      self.cycle(&racers, 1);
+    }
+    if pressed.contains(&Actions::SpectatePrevious) {
+      self.cycle(&racers, -1);
+    }
+
+    let (position, front) = match self.mode {
+      SpectatorMode::FreeFly => self.fly(&input, &settings, delta_time),
+      SpectatorMode::Follow | SpectatorMode::Leader => {
+        let target = match self.mode {
+          SpectatorMode::Leader => racers
+            .iter()
+            .filter(|(_, _, racer)| !racer.finished)
+            .min_by_key(|(_, _, racer)| racer.place)
+            .or_else(|| racers.first()),
+          _ => racers
+            .iter()
+            .find(|(entity, _, _)| Some(*entity) == self.followed)
+            .or_else(|| racers.first()),
+        };
+        let Some((entity, transform, _)) = target else {
+          return;
+        };
+        self.followed = Some(*entity);
+        self.follow(transform, &settings, delta_time)
+      }
+    };
+
+    if let Some(camera) = backpack.get_mut::<CameraConfig>() {
+      camera.translation = position;
+      camera.front = front;
+      camera.up = Unit::new_normalize(Vector3::y());
+    }
+  }
+}
+
+impl SpectatorCameraSystem {
+  /// Actions that went down this frame. Holding a key only counts once.
+  fn pressed(&mut self, input: &PlayerInput) -> HashSet<Actions> {
+    let pressed = input.actions.difference(&self.held).cloned().collect();
+    self.held = input.actions.clone();
+    pressed
+  }
+
+  fn cycle(&mut self, racers: &[(Entity, TransformComponent, RacerComponent)], step: isize) {
+    if racers.is_empty() {
+      return;
+    }
+    let current = racers
+      .iter()
+      .position(|(entity, _, _)| Some(*entity) == self.followed)
+      .unwrap_or(0) as isize;
+    let next = (current + step).rem_euclid(racers.len() as isize) as usize;
+
+    self.followed = Some(racers[next].0);
+    self.mode = SpectatorMode::Follow;
+  }
+
+  fn follow(
+    &mut self,
+    transform: &TransformComponent,
+    settings: &SpectatorCameraComponent,
+    delta_time: f32,
+  ) -> (Vector3<f32>, Unit<Vector3<f32>>) {
+    let behind = -transform.get_euler_direction().into_inner();
+    let desired_position = transform.translation
+      + behind * settings.follow_distance
+      + Vector3::new(0.0, settings.follow_height, 0.0);
+
+    let position = match self.position {
+      Some(position) => position.lerp(
+        &desired_position,
+        1.0 - f32::exp(-settings.follow_damping * delta_time),
+      ),
+      None => desired_position,
+    };
+    self.position = Some(position);
+
+    let front = Unit::try_new(transform.translation - position, f32::EPSILON)
+      .unwrap_or_else(|| Unit::new_normalize(-behind));
+
+    // free-fly picks up from wherever the camera was looking
+    self.yaw = f32::atan2(front.x, front.z);
+    self.pitch = front.y.asin();
+
+    (position, front)
+  }
+
+  fn fly(
+    &mut self,
+    input: &PlayerInput,
+    settings: &SpectatorCameraComponent,
+    delta_time: f32,
+  ) -> (Vector3<f32>, Unit<Vector3<f32>>) {
+    if input.mouse_lock {
+      self.yaw -= input.mouse_delta.x * settings.look_sensitivity;
+      self.pitch = (self.pitch - input.mouse_delta.y * settings.look_sensitivity).clamp(-1.5, 1.5);
+    }
+
+    let front = Rotation3::from_euler_angles(0.0, self.yaw, 0.0)
+      * Rotation3::from_euler_angles(-self.pitch, 0.0, 0.0)
+      * Vector3::z();
+    let front = Unit::new_normalize(front);
+    let right = Unit::try_new(front.cross(&Vector3::y()), f32::EPSILON)
+      .map(|right| right.into_inner())
+      .unwrap_or_else(Vector3::x);
+
+    let movement = front.into_inner() * input.direction_vector.z + right * input.direction_vector.x;
+    let position = self.position.unwrap_or_default() + movement * settings.fly_speed * delta_time;
+    self.position = Some(position);
+
+    (position, front)
+  }
+}
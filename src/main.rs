@@ -23,6 +23,9 @@ pub async fn init_main(
   access_token: String,
   udp_url: String,
   tcp_url: String,
+  board: Option<String>,
+  avatar: Option<String>,
+  spectate: bool,
 ) {
   client::main(
     id,
@@ -32,10 +35,53 @@ pub async fn init_main(
     access_token,
     udp_url,
     tcp_url,
+    board,
+    avatar,
+    spectate,
   )
   .await;
 }
 
+/// Leaves the race to watch it, or asks to rejoin it once it takes racers.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_spectating(spectating: bool) {
+  shared::loadout::set_spectating(spectating);
+}
+
+/// Starts playing back a replay file saved by the server.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn play_replay(bytes: Vec<u8>) -> Result<(), JsValue> {
+  let replay = shared::replay::Replay::decode(&bytes).map_err(|error| JsValue::from_str(&error))?;
+  client::replay::send(client::replay::ReplayCommand::Play(replay));
+  Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn stop_replay() {
+  client::replay::send(client::replay::ReplayCommand::Stop);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn pause_replay(paused: bool) {
+  client::replay::send(client::replay::ReplayCommand::Pause(paused));
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn seek_replay(seconds: f32) {
+  client::replay::send(client::replay::ReplayCommand::Seek(seconds));
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn set_replay_speed(speed: f32) {
+  client::replay::send(client::replay::ReplayCommand::Speed(speed));
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::main]
 pub async fn main() {
@@ -0,0 +1,261 @@
+use engine::networking::connection::PlayerId;
+use engine::systems::input::InputsReader;
+use nalgebra::Vector3;
+use std::collections::HashMap;
+
+use crate::shared::components::Ability;
+use crate::shared::input::{Actions, PlayerInput};
+
+/// How close behind a bot another board has to be for a smoke bomb to be
+/// worth it.
+const SMOKE_BOMB_RANGE: f32 = 12.0;
+/// Bots only boost, with the meter or an item, while their next checkpoint
+/// is within this many radians of straight ahead.
+const STRAIGHT_ANGLE: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotDifficulty {
+  Easy,
+  Medium,
+  Hard,
+}
+
+impl BotDifficulty {
+  fn parse(difficulty: &str) -> Option<Self> {
+    match difficulty {
+      "easy" => Some(Self::Easy),
+      "medium" => Some(Self::Medium),
+      "hard" => Some(Self::Hard),
+      _ => None,
+    }
+  }
+
+  fn profile(&self) -> BotProfile {
+    match self {
+      Self::Easy => BotProfile {
+        throttle: 0.7,
+        look_ahead: 0.0,
+        steering_gain: 1.0,
+        wobble: 0.3,
+        avoid_radius: 3.0,
+        smoke_bomb_chance: 0.02,
+        brake_angle: None,
+        boost_above: None,
+      },
+      Self::Medium => BotProfile {
+        throttle: 0.85,
+        look_ahead: 0.3,
+        steering_gain: 1.5,
+        wobble: 0.15,
+        avoid_radius: 4.0,
+        smoke_bomb_chance: 0.05,
+        brake_angle: Some(1.2),
+        boost_above: Some(0.8),
+      },
+      Self::Hard => BotProfile {
+        throttle: 1.0,
+        look_ahead: 0.5,
+        steering_gain: 2.5,
+        wobble: 0.05,
+        avoid_radius: 5.0,
+        smoke_bomb_chance: 0.2,
+        brake_angle: Some(0.9),
+        boost_above: Some(0.4),
+      },
+    }
+  }
+}
+
+/// How a difficulty level drives.
+#[derive(Debug, Clone, Copy)]
+struct BotProfile {
+  /// Forward input, out of 1.
+  throttle: f32,
+  /// How far the aim point is pulled from the next checkpoint towards the
+  /// one after it, cutting corners.
+  look_ahead: f32,
+  /// Steering input per radian off the aim point.
+  steering_gain: f32,
+  /// Random steering noise, out of 1.
+  wobble: f32,
+  /// Boards closer than this in front get steered around.
+  avoid_radius: f32,
+  /// Chance per second of throwing a smoke bomb at a board close behind.
+  smoke_bomb_chance: f32,
+  /// Brakes into corners sharper than this many radians, if set.
+  brake_angle: Option<f32>,
+  /// Boosts down straights once the meter is at least this full, if set.
+  boost_above: Option<f32>,
+}
+
+#[derive(Debug, Default)]
+struct BotState {
+  input: PlayerInput,
+}
+
+/// What a bot can see of the world on a given tick.
+pub struct BotView<'a> {
+  pub position: Vector3<f32>,
+  pub forward: Vector3<f32>,
+  /// Current speed as a fraction of the board's top speed.
+  pub speed_ratio: f32,
+  /// How full the boost meter is, out of 1.
+  pub boost: f32,
+  pub airborne: bool,
+  /// How far the board leans off the rail it's grinding, if any.
+  pub balance: Option<f32>,
+  pub item: Ability,
+  pub next_checkpoint: Option<Vector3<f32>>,
+  pub following_checkpoint: Option<Vector3<f32>>,
+  pub others: &'a [Vector3<f32>],
+}
+
+/// Server-side racers that fill a session up to `BOT_COUNT` racers (0 by
+/// default), driving at `BOT_DIFFICULTY` (`easy`, `medium` or `hard`).
+pub struct Bots {
+  pub count: usize,
+  pub difficulty: BotDifficulty,
+  bots: HashMap<PlayerId, BotState>,
+}
+
+impl Bots {
+  pub fn from_env() -> Self {
+    let count = dotenv::var("BOT_COUNT")
+      .ok()
+      .and_then(|count| count.parse::<usize>().ok())
+      .unwrap_or(0);
+    let difficulty = dotenv::var("BOT_DIFFICULTY")
+      .ok()
+      .and_then(|difficulty| BotDifficulty::parse(&difficulty))
+      .unwrap_or(BotDifficulty::Medium);
+
+    Self {
+      count,
+      difficulty,
+      bots: HashMap::new(),
+    }
+  }
+
+  pub fn insert(&mut self, player_id: PlayerId) {
+    self.bots.insert(player_id, BotState::default());
+  }
+
+  pub fn remove(&mut self, player_id: &PlayerId) {
+    self.bots.remove(player_id);
+  }
+
+  /// The input the bot produced on the last tick.
+  pub fn input(&self, player_id: &PlayerId) -> Option<&PlayerInput> {
+    self.bots.get(player_id).map(|bot| &bot.input)
+  }
+
+  /// Works out the bot's input for this tick.
+  pub fn think(&mut self, player_id: &PlayerId, view: &BotView, delta_time: f32) -> PlayerInput {
+    let profile = self.difficulty.profile();
+    let Some(bot) = self.bots.get_mut(player_id) else {
+      return PlayerInput::default();
+    };
+
+    let mut input = PlayerInput::default();
+
+    let Some(next_checkpoint) = view.next_checkpoint else {
+      bot.input = input.clone();
+      return input;
+    };
+    let aim = match view.following_checkpoint {
+      Some(following) => next_checkpoint.lerp(&following, profile.look_ahead),
+      None => next_checkpoint,
+    };
+
+    let mut steering = -signed_angle(&view.forward, &(aim - view.position)) * profile.steering_gain;
+
+    // steer around boards just ahead, harder the closer they are
+    for other in view.others {
+      let to_other = other - view.position;
+      let distance = to_other.norm();
+      if distance > profile.avoid_radius || to_other.dot(&view.forward) <= 0.0 {
+        continue;
+      }
+      let side = signed_angle(&view.forward, &to_other).signum();
+      steering += side * (1.0 - distance / profile.avoid_radius);
+    }
+
+    steering += (rand::random::<f32>() * 2.0 - 1.0) * profile.wobble;
+    // on a rail, steering only keeps the board balanced
+    if let Some(balance) = view.balance {
+      steering = -balance * profile.steering_gain;
+    }
+    input.direction_vector.x = steering.clamp(-1.0, 1.0);
+    // throttle tips the nose in the air, so let go until landing
+    input.direction_vector.z = if view.airborne { 0.0 } else { profile.throttle };
+
+    let corner = signed_angle(&view.forward, &(next_checkpoint - view.position)).abs();
+    if let Some(brake_angle) = profile.brake_angle
+      && corner > brake_angle
+      && view.speed_ratio > 0.6
+    {
+      input.actions.insert(Actions::Brake);
+    }
+
+    // keep the meter for the straights
+    if let Some(boost_above) = profile.boost_above
+      && view.boost >= boost_above
+      && corner < STRAIGHT_ANGLE
+    {
+      input.actions.insert(Actions::Boost);
+    }
+
+    let chased = view.others.iter().any(|other| {
+      let to_other = other - view.position;
+      to_other.norm() < SMOKE_BOMB_RANGE && to_other.dot(&view.forward) < 0.0
+    });
+    let crowded = view
+      .others
+      .iter()
+      .any(|other| (other - view.position).norm() < profile.avoid_radius);
+    let use_item = match view.item {
+      Ability::None => false,
+      Ability::SmokeBomb => {
+        chased && rand::random::<f32>() < profile.smoke_bomb_chance * delta_time
+      }
+      Ability::Boost => corner < STRAIGHT_ANGLE,
+      Ability::Shield => crowded,
+    };
+    if use_item {
+      input.actions.insert(Actions::UseItem);
+    }
+
+    bot.input = input.clone();
+    input
+  }
+}
+
+/// What a racer is pressing this tick. Bots don't send inputs, so theirs is
+/// the one they last drove with.
+pub fn racer_input(
+  inputs: &InputsReader<PlayerInput>,
+  bots: Option<&Bots>,
+  player_id: &PlayerId,
+) -> Option<PlayerInput> {
+  match bots.and_then(|bots| bots.input(player_id)) {
+    Some(input) => Some(input.clone()),
+    None => inputs.read_for(player_id),
+  }
+}
+
+/// Angle from `from` to `to` around the up axis, ignoring height. Positive
+/// when `to` is on the side positive steering turns away from.
+fn signed_angle(from: &Vector3<f32>, to: &Vector3<f32>) -> f32 {
+  let from = Vector3::new(from.x, 0.0, from.z);
+  let to = Vector3::new(to.x, 0.0, to.z);
+  if from.norm() < f32::EPSILON || to.norm() < f32::EPSILON {
+    return 0.0;
+  }
+
+  let angle = from.angle(&to);
+  if from.cross(&to).y >= 0.0 {
+    angle
+  } else {
+    -angle
+  }
+}
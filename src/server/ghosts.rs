@@ -0,0 +1,174 @@
+use engine::networking::connection::PlayerId;
+use engine::Entity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::shared::replay::Replay;
+
+/// A single lap, traced with the replay format. The trace has one racer and
+/// its frame times start at 0 when the lap does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhostLap {
+  pub time: f32,
+  /// Lap time at each checkpoint, start/finish line excluded.
+  pub splits: Vec<f32>,
+  pub trace: Replay,
+}
+
+impl GhostLap {
+  pub fn owner(&self) -> Option<Uuid> {
+    self.trace.racers.first().map(|racer| racer.id)
+  }
+}
+
+/// Best laps on the current level, per player and overall, kept on disk under
+/// `GHOST_DIR` (`ghosts` by default) so they outlive the session. Setting
+/// `GHOST_DIR` to an empty string keeps them in memory only.
+pub struct GhostStore {
+  directory: Option<PathBuf>,
+  level: String,
+  personal: HashMap<Uuid, GhostLap>,
+  track_best: Option<GhostLap>,
+}
+
+impl GhostStore {
+  pub fn from_env(level: &str) -> Self {
+    let directory = match dotenv::var("GHOST_DIR") {
+      Ok(directory) if directory.is_empty() => None,
+      Ok(directory) => Some(PathBuf::from(directory)),
+      Err(_) => Some(PathBuf::from("ghosts")),
+    };
+
+    let mut store = Self {
+      directory,
+      level: String::new(),
+      personal: HashMap::new(),
+      track_best: None,
+    };
+    store.load(level);
+    store
+  }
+
+  pub fn level(&self) -> &str {
+    &self.level
+  }
+
+  /// Swaps in the laps recorded on `level`.
+  pub fn load(&mut self, level: &str) {
+    self.level = level.to_string();
+    self.personal.clear();
+    self.track_best = None;
+
+    let Some(directory) = self.level_directory() else {
+      return;
+    };
+    let Ok(entries) = std::fs::read_dir(&directory) else {
+      return;
+    };
+
+    for entry in entries.flatten() {
+      let path = entry.path();
+      if path.extension().and_then(|extension| extension.to_str()) != Some("ghost") {
+        continue;
+      }
+
+      let lap = match std::fs::read(&path)
+        .map_err(|error| error.to_string())
+        .and_then(|bytes| {
+          bincode::deserialize::<GhostLap>(&bytes).map_err(|error| error.to_string())
+        }) {
+        Ok(lap) => lap,
+        Err(error) => {
+          log::warn!("skipping ghost {:?}: {}", path, error);
+          continue;
+        }
+      };
+
+      if path.file_stem().and_then(|stem| stem.to_str()) == Some("best") {
+        self.track_best = Some(lap);
+      } else if let Some(owner) = lap.owner() {
+        self.personal.insert(owner, lap);
+      }
+    }
+
+    log::info!(
+      "loaded {} personal ghosts for {:?}",
+      self.personal.len(),
+      self.level
+    );
+  }
+
+  pub fn personal(&self, player: &Uuid) -> Option<&GhostLap> {
+    self.personal.get(player)
+  }
+
+  pub fn track_best(&self) -> Option<&GhostLap> {
+    self.track_best.as_ref()
+  }
+
+  /// Keeps the lap if it beats the player's best or the track's best.
+  pub fn submit(&mut self, player: Uuid, lap: GhostLap) {
+    let beats = |best: Option<&GhostLap>| best.map_or(true, |best| lap.time < best.time);
+
+    if beats(self.track_best.as_ref()) {
+      log::info!("new best lap on {:?}: {:.3}s", self.level, lap.time);
+      self.save("best", &lap);
+      self.track_best = Some(lap.clone());
+    }
+
+    if beats(self.personal.get(&player)) {
+      self.save(&player.to_string(), &lap);
+      self.personal.insert(player, lap);
+    }
+  }
+
+  fn level_directory(&self) -> Option<PathBuf> {
+    let directory = self.directory.as_ref()?;
+    Some(directory.join(self.level.trim_end_matches(".lvl")))
+  }
+
+  fn save(&self, name: &str, lap: &GhostLap) {
+    let Some(directory) = self.level_directory() else {
+      return;
+    };
+    let path = directory.join(format!("{name}.ghost"));
+
+    let bytes = bincode::serialize(lap).expect("ghost lap is always serializable");
+    let result = std::fs::create_dir_all(&directory).and_then(|_| std::fs::write(&path, bytes));
+    if let Err(error) = result {
+      log::error!("could not save ghost {:?}: {}", path, error);
+    }
+  }
+}
+
+/// The ghost entities spawned for each player, so a full resync can send them
+/// again to the player racing them and to nobody else.
+#[derive(Default)]
+pub struct GhostEntities {
+  entities: HashMap<PlayerId, Vec<Entity>>,
+}
+
+impl GhostEntities {
+  pub fn insert(&mut self, player_id: PlayerId, entities: Vec<Entity>) {
+    self.entities.insert(player_id, entities);
+  }
+
+  pub fn remove(&mut self, player_id: &PlayerId) -> Vec<Entity> {
+    self.entities.remove(player_id).unwrap_or_default()
+  }
+
+  pub fn get(&self, player_id: &PlayerId) -> &[Entity] {
+    self.entities.get(player_id).map_or(&[], Vec::as_slice)
+  }
+
+  /// Forgets every ghost, handing back their entities.
+  pub fn drain(&mut self) -> Vec<Entity> {
+    self
+      .entities
+      .drain()
+      .flat_map(|(_, entities)| entities)
+      .collect()
+  }
+}
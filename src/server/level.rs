@@ -0,0 +1,403 @@
+use engine::{
+  application::{
+    assets::Store,
+    config::Config,
+    downloader::DownloadSender,
+    gamefile::Gamefile,
+    scene::{Prefab, Scene, TransformComponent},
+  },
+  Entity,
+};
+use nalgebra::Vector3;
+use std::collections::HashMap;
+
+use crate::server::track::TrackSpline;
+use crate::shared::components::{
+  CheckpointComponent, Role, TrackBoundsComponent, TrackSplineComponent,
+};
+use crate::shared::prefabs::{prefab_component, prefab_role};
+use crate::shared::volume::BoxVolume;
+
+const DEFAULT_LEVEL: &str = "arena.lvl";
+
+/// Level prefabs that aren't instantiated straight into the world, grouped
+/// by the role they declare through their `RoleComponent`.
+#[derive(Default)]
+pub struct LevelPrefabs {
+  by_role: HashMap<Role, Vec<Prefab>>,
+}
+
+impl LevelPrefabs {
+  pub fn insert(&mut self, role: Role, prefab: Prefab) {
+    self.by_role.entry(role).or_default().push(prefab);
+  }
+
+  pub fn iter(&self, role: Role) -> impl Iterator<Item = &Prefab> {
+    self.by_role.get(&role).into_iter().flatten()
+  }
+
+  pub fn first(&self, role: Role) -> Option<&Prefab> {
+    self.iter(role).next()
+  }
+
+  pub fn find(&self, role: Role, name: &str) -> Option<&Prefab> {
+    self.iter(role).find(|prefab| prefab.tag.name == name)
+  }
+
+  /// Avatars, boards, characters and their ghost looks: everything clients
+  /// need to dress up racers they spawn themselves, like replay stand-ins.
+  pub fn catalog(&self) -> impl Iterator<Item = &Prefab> {
+    const CATALOG: [Role; 4] = [
+      Role::PlayerAvatar,
+      Role::Board,
+      Role::Character,
+      Role::Ghost,
+    ];
+    CATALOG.iter().flat_map(move |role| self.iter(*role))
+  }
+}
+
+/// A point racers have to pass, in order, to complete a lap.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+  pub index: u32,
+  pub position: Vector3<f32>,
+  pub radius: f32,
+  /// How far along the track the checkpoint sits, when the level has one.
+  pub distance: Option<f32>,
+}
+
+/// Ordered list of levels the server cycles through between races.
+///
+/// The starting level comes from `--level <file>` or the `LEVEL` env var, and
+/// the rotation from `--rotation a.lvl,b.lvl` or `LEVEL_ROTATION`. A starting
+/// level that isn't part of the rotation is played first, then the rotation
+/// takes over.
+pub struct LevelRotation {
+  levels: Vec<String>,
+  current: usize,
+}
+
+impl LevelRotation {
+  pub fn from_env() -> Self {
+    let mut level = None;
+    let mut rotation = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+      match arg.as_str() {
+        "--level" => level = args.next(),
+        "--rotation" => rotation = args.next(),
+        _ => {}
+      }
+    }
+
+    let level = level.or_else(|| dotenv::var("LEVEL").ok());
+    let rotation = rotation.or_else(|| dotenv::var("LEVEL_ROTATION").ok());
+
+    Self::new(level, rotation)
+  }
+
+  pub fn new(level: Option<String>, rotation: Option<String>) -> Self {
+    let mut levels: Vec<String> = rotation
+      .unwrap_or_default()
+      .split(',')
+      .map(|name| name.trim())
+      .filter(|name| !name.is_empty())
+      .map(String::from)
+      .collect();
+
+    let current = match level {
+      Some(level) => match levels.iter().position(|name| *name == level) {
+        Some(index) => index,
+        None => {
+          levels.insert(0, level);
+          0
+        }
+      },
+      None => 0,
+    };
+
+    if levels.is_empty() {
+      levels.push(DEFAULT_LEVEL.to_string());
+    }
+
+    Self { levels, current }
+  }
+
+  pub fn current(&self) -> &str {
+    &self.levels[self.current]
+  }
+
+  pub fn advance(&mut self) -> &str {
+    self.current = (self.current + 1) % self.levels.len();
+    self.current()
+  }
+}
+
+/// Everything the server loaded from the current `.lvl` file. Lives in the
+/// backpack so the network controller and the server systems share it.
+pub struct Level {
+  pub name: String,
+  pub config: Config,
+  pub store: Store,
+  pub prefabs: LevelPrefabs,
+  pub spawn_points: Vec<TransformComponent>,
+  /// Sorted by index, so the start/finish line comes first.
+  pub checkpoints: Vec<Checkpoint>,
+  /// The racing line, for levels that have one.
+  pub track: Option<TrackSpline>,
+  /// Where racers are allowed to be. Empty when the level doesn't say.
+  pub bounds: Vec<BoxVolume>,
+  entities: Vec<Entity>,
+  playable: bool,
+}
+
+impl Level {
+  pub fn load(download_sender: &DownloadSender, scene: &mut Scene, name: &str) -> Self {
+    log::info!("loading level {:?}", name);
+
+    let gamefile = Gamefile::from_file(download_sender, name);
+
+    let mut store = Store::new();
+    for (id, terrain) in gamefile.scene.terrains {
+      store.insert_asset(id, terrain);
+    }
+    for (id, model) in gamefile.scene.models {
+      store.insert_asset(id, model);
+    }
+    for (id, trimesh) in gamefile.scene.trimeshes {
+      store.insert_asset(id, trimesh);
+    }
+    for (id, background) in gamefile.scene.dynamic_backgrounds {
+      store.insert_asset(id, background);
+    }
+    for (id, animation) in gamefile.scene.animations {
+      store.insert_asset(id, animation);
+    }
+
+    let mut prefabs = LevelPrefabs::default();
+    let mut spawn_points = vec![];
+    let mut checkpoints = vec![];
+    let mut track = None;
+    let mut bounds = vec![];
+    let mut entities = vec![];
+
+    for (_, prefab) in gamefile.scene.prefabs {
+      match prefab_role(&prefab) {
+        Some(Role::SpawnPoint) => {
+          spawn_points.push(prefab.transform.clone());
+        }
+        Some(Role::Checkpoint) => {
+          let Some(checkpoint) = prefab_component::<CheckpointComponent>(&prefab) else {
+            log::warn!(
+              "checkpoint {:?} has no CheckpointComponent",
+              prefab.tag.name
+            );
+            continue;
+          };
+          checkpoints.push(Checkpoint {
+            index: checkpoint.index,
+            position: prefab.transform.translation,
+            radius: checkpoint.radius,
+            distance: None,
+          });
+        }
+        Some(Role::TrackSpline) => {
+          let spline = prefab_component::<TrackSplineComponent>(&prefab).and_then(TrackSpline::new);
+          match spline {
+            Some(spline) => {
+              log::info!(
+                "track {:?} is {:.0}m long",
+                prefab.tag.name,
+                spline.length()
+              );
+              track = Some(spline);
+            }
+            None => log::warn!(
+              "track spline {:?} needs a TrackSplineComponent with at least two points",
+              prefab.tag.name
+            ),
+          }
+        }
+        Some(Role::TrackBounds) => {
+          let Some(volume) = prefab_component::<TrackBoundsComponent>(&prefab) else {
+            log::warn!(
+              "track bounds {:?} have no TrackBoundsComponent",
+              prefab.tag.name
+            );
+            continue;
+          };
+          bounds.push(BoxVolume::new(&prefab.transform, volume.half_extents));
+        }
+        Some(
+          role @ (Role::PlayerAvatar
+          | Role::Board
+          | Role::Character
+          | Role::AbilityEffect
+          | Role::Ghost),
+        ) => {
+          log::info!("creating {:?} prefab: {:?}", role, prefab.tag.name);
+          prefabs.insert(role, prefab);
+        }
+        role => {
+          if role == Some(Role::Unknown) {
+            log::warn!(
+              "prefab {:?} has an unknown role, spawning it as a world object",
+              prefab.tag.name
+            );
+          }
+          log::info!("receiving entity {:?}", prefab.tag.name);
+          let entity = scene.create_raw_entity("tmp");
+          scene.create_with_prefab(entity, prefab);
+          entities.push(entity);
+        }
+      }
+    }
+
+    let mut playable = true;
+    for role in [Role::PlayerAvatar, Role::Board] {
+      if prefabs.first(role).is_none() {
+        log::warn!(
+          "level {:?} has no {:?} prefab, so nobody can race on it",
+          name,
+          role
+        );
+        playable = false;
+      }
+    }
+
+    // with a single checkpoint, the start line is also the next one, and
+    // racers standing on it would complete a lap every tick
+    if checkpoints.len() == 1 {
+      log::warn!(
+        "level {:?} has a single checkpoint, laps need at least two so it is ignored",
+        name
+      );
+      checkpoints.clear();
+    }
+    checkpoints.sort_by_key(|checkpoint| checkpoint.index);
+    if let Some(track) = &track {
+      for checkpoint in &mut checkpoints {
+        checkpoint.distance = Some(track.distance_along(&checkpoint.position));
+      }
+    }
+
+    Self {
+      name: name.to_string(),
+      config: gamefile.config,
+      store,
+      prefabs,
+      spawn_points,
+      checkpoints,
+      track,
+      bounds,
+      entities,
+      playable,
+    }
+  }
+
+  /// Loads the rotation's current level, moving on through the rotation past
+  /// levels racers can't be spawned on. When none of them can, the last one
+  /// tried is kept and everyone spectates.
+  pub fn load_playable(
+    download_sender: &DownloadSender,
+    scene: &mut Scene,
+    rotation: &mut LevelRotation,
+  ) -> Self {
+    let mut level = Self::load(download_sender, scene, rotation.current());
+    for _ in 1..rotation.levels.len() {
+      if level.is_playable() {
+        break;
+      }
+      log::warn!("skipping level {:?}", level.name);
+      level.unload(scene);
+      level = Self::load(download_sender, scene, rotation.advance());
+    }
+    level
+  }
+
+  /// Whether the level has the avatar and board prefabs racers are spawned
+  /// from.
+  pub fn is_playable(&self) -> bool {
+    self.playable
+  }
+
+  /// Whether `position` is inside the level's track bounds, if it has any.
+  pub fn in_bounds(&self, position: &Vector3<f32>) -> bool {
+    self.bounds.is_empty() || self.bounds.iter().any(|bounds| bounds.contains(position))
+  }
+
+  /// Where the player in the given slot starts. Falls back to the avatar
+  /// prefab's own transform when the level has no spawn points.
+  pub fn spawn_point(&self, slot: usize) -> Option<&TransformComponent> {
+    if self.spawn_points.is_empty() {
+      return None;
+    }
+    self.spawn_points.get(slot % self.spawn_points.len())
+  }
+
+  /// Where the player in the given slot starts, falling back to the avatar
+  /// prefab's own transform.
+  pub fn start_transform(&self, slot: usize) -> Option<TransformComponent> {
+    self
+      .spawn_point(slot)
+      .or_else(|| {
+        self
+          .prefabs
+          .first(Role::PlayerAvatar)
+          .map(|prefab| &prefab.transform)
+      })
+      .cloned()
+  }
+
+  pub fn board(&self, name: Option<&str>) -> Option<&Prefab> {
+    self.pick(Role::Board, name)
+  }
+
+  pub fn character(&self, name: Option<&str>) -> Option<&Prefab> {
+    self.pick(Role::Character, name)
+  }
+
+  /// The name of the ghost look for a board or character prefab, if the
+  /// level has one.
+  pub fn ghost_look(&self, prefab: &Prefab) -> Option<String> {
+    let look = format!("{} Ghost", prefab.tag.name);
+    self.prefabs.find(Role::Ghost, &look).map(|_| look)
+  }
+
+  /// Looks a loadout pick up in the level's catalog for that role. Unknown or
+  /// missing picks fall back to the first entry so a stale client can still
+  /// race.
+  fn pick(&self, role: Role, name: Option<&str>) -> Option<&Prefab> {
+    if let Some(name) = name {
+      match self.prefabs.find(role, name) {
+        Some(prefab) => return Some(prefab),
+        None => log::warn!("{:?} {:?} is not in level {:?}", role, name, self.name),
+      }
+    }
+    self.prefabs.first(role)
+  }
+
+  /// Removes every world entity this level spawned and lets go of its
+  /// assets and prefabs, so nothing of it is sent to clients any more.
+  /// Players and their boards are owned by the session, not the level, and
+  /// are left alone.
+  pub fn unload(&mut self, scene: &mut Scene) {
+    log::info!(
+      "unloading level {:?} and its {} assets",
+      self.name,
+      self.store.iter_assets().count()
+    );
+
+    for entity in self.entities.drain(..) {
+      let _ = scene.despawn(entity);
+    }
+    self.store = Store::new();
+    self.prefabs = LevelPrefabs::default();
+    self.spawn_points.clear();
+    self.checkpoints.clear();
+    self.track = None;
+    self.bounds.clear();
+  }
+}
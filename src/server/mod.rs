@@ -1,4 +1,13 @@
+mod bots;
+mod ghosts;
+mod level;
 mod network_controller;
+mod players;
+mod race;
+mod replay;
+mod systems;
+mod track;
+mod tricks;
 
 use engine::application::scene::Prefab;
 use engine::systems::Inventory;
@@ -6,7 +15,23 @@ use engine::systems::Plugin;
 use engine::systems::{hdr::HdrPipeline, network::NetworkPlugin, Scheduler};
 
 use crate::server::network_controller::NetworkController;
-use crate::shared::components::PlayerMovementComponent;
+use crate::server::systems::boost_meter::BoostMeterSystem;
+use crate::server::systems::bots::BotSystem;
+use crate::server::systems::bumps::BumpSystem;
+use crate::server::systems::ghosts::GhostSystem;
+use crate::server::systems::items::ItemSystem;
+use crate::server::systems::lap_timer::LapTimerSystem;
+use crate::server::systems::level_rotation::LevelRotationSystem;
+use crate::server::systems::loadout::LoadoutSystem;
+use crate::server::systems::race::RaceSystem;
+use crate::server::systems::ranking::RankingSystem;
+use crate::server::systems::recovery::RecoverySystem;
+use crate::server::systems::replay::ReplaySystem;
+use crate::server::systems::time_trial::TimeTrialSystem;
+use crate::server::systems::tricks::TrickSystem;
+use crate::server::systems::walls::WallSystem;
+use crate::shared::components::register_components;
+use crate::shared::systems::expiry::ExpirySystem;
 
 use engine::application::scene::component_registry::Access;
 
@@ -21,7 +46,7 @@ impl Plugin for CustomComponentsPlugin {
   async fn init(mut self: Box<Self>, _: &mut Scheduler) {}
 
   fn provide(&mut self, _: &Inventory) {
-    PlayerMovementComponent::register();
+    register_components();
   }
 }
 
@@ -29,6 +54,10 @@ pub async fn main() {
   dotenv::dotenv().ok();
   env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
+  if replay::play_from_args() {
+    return;
+  }
+
   let rpc_address = {
     let address = dotenv::var("RPC_ADDRESS").unwrap();
     let port = dotenv::var("RPC_PORT").unwrap();
@@ -49,6 +78,22 @@ pub async fn main() {
   let mut runner = Scheduler::new(FRAMES_PER_SECOND);
   runner.attach_plugin(hdr);
   runner.attach_plugin(CustomComponentsPlugin);
+  runner.attach_system::<RaceSystem>();
+  runner.attach_system::<RankingSystem>();
+  runner.attach_system::<BotSystem>();
+  runner.attach_system::<RecoverySystem>();
+  runner.attach_system::<BumpSystem>();
+  runner.attach_system::<WallSystem>();
+  runner.attach_system::<ItemSystem>();
+  runner.attach_system::<BoostMeterSystem>();
+  runner.attach_system::<TrickSystem>();
+  runner.attach_system::<TimeTrialSystem>();
+  runner.attach_system::<LapTimerSystem>();
+  runner.attach_system::<ReplaySystem>();
+  runner.attach_system::<GhostSystem>();
+  runner.attach_system::<LevelRotationSystem>();
+  runner.attach_system::<LoadoutSystem>();
+  runner.attach_system::<ExpirySystem>();
 
   runner.run().await;
 
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
-use engine::application::components::ParentComponent;
 use engine::application::components::SelfComponent;
+use engine::application::components::{AnimationComponent, ModelComponent, ParentComponent};
 use engine::systems::Backpack;
 use engine::{
   application::{
@@ -29,170 +29,334 @@ use nalgebra::Vector3;
 use std::collections::HashMap;
 use uuid::Uuid;
 
-#[derive(Debug, Eq, PartialEq, Hash)]
-enum ModelNames {
-  Player,
-  SmokeBomb,
-  Hoverboard,
-}
-
-impl ModelNames {
-  fn from_str(name: &str) -> Self {
-    match name {
-      "Player" => Self::Player,
-      "Smoke Bomb" => Self::SmokeBomb,
-      "Hoverboard" => Self::Hoverboard,
-      _ => panic!("Unknown model name: {}", name),
-    }
-  }
-
-  fn to_str(&self) -> &str {
-    match self {
-      Self::Player => "Player",
-      Self::SmokeBomb => "Smoke Bomb",
-      Self::Hoverboard => "Hoverboard",
-    }
-  }
-}
+use crate::server::bots::Bots;
+use crate::server::ghosts::{GhostEntities, GhostStore};
+use crate::server::level::{Level, LevelRotation};
+use crate::server::players::{PlayerEntities, Players};
+use crate::server::race::Race;
+use crate::server::replay::ReplayRecorder;
+use crate::shared::components::{
+  AnimationControllerComponent, BoardComponent, GhostComponent, PlayerMovementComponent,
+  RacerComponent, Role,
+};
+use crate::shared::loadout::Loadout;
+use crate::shared::prefabs::prefab_component;
 
 pub struct NetworkController {
-  prefabs: HashMap<ModelNames, Prefab>,
   download_sender: DownloadSender,
   client_sender: ClientSender<TrustedInput>,
-  config: Option<Config>,
-  store: Store,
 }
 
 impl Initializable for NetworkController {
   fn initialize(inventory: &Inventory) -> Self {
     let download_sender = inventory.get::<DownloadSender>().clone();
     let client_sender = inventory.get::<ClientSender<TrustedInput>>().clone();
-    let store = Store::new();
     Self {
       client_sender,
       download_sender,
-      store,
-      prefabs: HashMap::new(),
-      config: None,
     }
   }
 }
 
-impl NetworkController {
-  fn sync_world(&self, scene: &mut Scene, player_id: &PlayerId) {
-    let mut definitions = vec![];
+/// Spawns the player with the avatar and board picked in its loadout from the
+/// current level's prefabs, at the spawn point reserved for `slot`. Spawns
+/// nothing on levels without an avatar or board prefab.
+pub fn spawn_player(
+  scene: &mut Scene,
+  level: &Level,
+  entity: Entity,
+  player_id: PlayerId,
+  slot: usize,
+  loadout: Loadout,
+) -> Option<PlayerEntities> {
+  if !level.is_playable() {
+    log::warn!(
+      "level {:?} has nothing to spawn {:?} as",
+      level.name,
+      player_id
+    );
+    return None;
+  }
+  let mut player_prefab: Prefab = level.prefabs.first(Role::PlayerAvatar)?.clone();
+  log::info!("Player joined! New prefab: {:#?}", &player_prefab);
 
-    for (id, definition) in self.store.iter_assets() {
-      let packed = AssetPack::pack(definition);
-      definitions.push(packed);
+  *player_prefab.id = PrefabId::with_id(*player_id);
+  if let Some(spawn_point) = level.spawn_point(slot) {
+    player_prefab.transform = spawn_point.clone();
+  }
+  scene.create_with_prefab(entity, player_prefab);
+
+  if let Ok(racer) = scene.query_one_mut::<&mut RacerComponent>(entity) {
+    racer.place = slot as u32 + 1;
+    racer.finished = false;
+  }
+
+  equip_character(scene, level, entity, loadout.avatar.as_deref());
+  let hoverboard = equip_board(
+    scene,
+    level,
+    entity,
+    PrefabId::with_id(*player_id),
+    loadout.board.as_deref(),
+  )?;
+
+  Some(PlayerEntities {
+    player: entity,
+    hoverboard,
+    loadout,
+    slot,
+  })
+}
+
+/// Puts a connected player into the race on `entity`, with the loadout they
+/// last picked. Players the level has nothing to spawn as spectate instead.
+pub fn join_race(scene: &mut Scene, backpack: &mut Backpack, entity: Entity, player_id: PlayerId) {
+  let Some(players) = backpack.get::<Players>() else {
+    return;
+  };
+  let slot = players.free_slot();
+  let loadout = players.loadout(&player_id);
+
+  let level = backpack.get::<Level>().unwrap();
+  let player_entities = spawn_player(scene, level, entity, player_id, slot, loadout);
+
+  let Some(players) = backpack.get_mut::<Players>() else {
+    return;
+  };
+  match player_entities {
+    Some(player_entities) => players.insert(player_id, player_entities),
+    None => {
+      let _ = scene.despawn(entity);
+      players.insert_spectator(player_id);
     }
+  }
+}
 
-    let mut world_entities = scene
-      .iter()
-      .map(|item| item.entity())
-      .collect::<Vec<Entity>>();
+/// Takes a racer out of the race, keeping them connected as a spectator.
+pub fn spectate(scene: &mut Scene, backpack: &mut Backpack, player_id: PlayerId) {
+  let Some(players) = backpack.get_mut::<Players>() else {
+    return;
+  };
 
-    let entities_data: Vec<_> = scene
-      .iter()
-      .map(|entity| entity.entity())
-      .collect::<Vec<Entity>>();
+  if let Some(player_entities) = players.remove(&player_id) {
+    let _ = scene.despawn(player_entities.player);
+    let _ = scene.despawn(player_entities.hoverboard);
+  }
+  players.insert_spectator(player_id);
+}
 
-    let mut entities = vec![];
+/// Seats a player whose join message arrived: in the race with the loadout
+/// they picked, or as a spectator when they asked to watch or the race no
+/// longer takes racers. Then sends them the world.
+pub fn admit_player(
+  client_sender: &ClientSender<TrustedInput>,
+  scene: &mut Scene,
+  backpack: &mut Backpack,
+  entity: Entity,
+  player_id: PlayerId,
+  loadout: Loadout,
+) {
+  let racers = backpack.get::<Players>().map_or(0, |players| players.len());
+  let race_full = backpack
+    .get::<Race>()
+    .map_or(false, |race| !race.accepts_racers(racers));
+  let spectating = loadout.spectate || race_full;
 
-    for entity in entities_data {
-      let mut prefab = Prefab::pack(scene, entity).unwrap();
-      let is_self = **player_id == **prefab.id;
-      if is_self {
-        prefab.components.push(Box::new(SelfComponent {}));
-      }
-      entities.push(prefab);
+  if let Some(players) = backpack.get_mut::<Players>() {
+    players.set_loadout(player_id, loadout);
+  }
+
+  if spectating {
+    log::info!("{player_id:?} joined as a spectator");
+
+    let _ = scene.despawn(entity);
+    if let Some(players) = backpack.get_mut::<Players>() {
+      players.insert_spectator(player_id);
     }
+  } else {
+    join_race(scene, backpack, entity, player_id);
+  }
+
+  sync_world(client_sender, scene, backpack, &player_id);
+}
+
+/// Spawns the requested board, reparents it to the player and copies its
+/// stats onto the player's movement component. Levels without any board
+/// prefab get nothing.
+pub fn equip_board(
+  scene: &mut Scene,
+  level: &Level,
+  player_entity: Entity,
+  player_prefab_id: PrefabId,
+  board: Option<&str>,
+) -> Option<Entity> {
+  let mut hoverboard_prefab: Prefab = level.board(board)?.clone();
+  let stats = prefab_component::<BoardComponent>(&hoverboard_prefab).copied();
+
+  let hoverboard_entity = scene.create_raw_entity("Hoverboard");
+  *hoverboard_prefab.id = PrefabId::new();
+  scene.create_with_prefab(hoverboard_entity, hoverboard_prefab);
+
+  if let parent_component = scene
+    .query_one_mut::<&mut ParentComponent>(hoverboard_entity)
+    .unwrap()
+  {
+    parent_component.id = player_prefab_id;
+  }
+
+  if let Some(stats) = stats
+    && let Ok(movement) = scene.query_one_mut::<&mut PlayerMovementComponent>(player_entity)
+  {
+    stats.apply(movement);
+  }
+
+  Some(hoverboard_entity)
+}
+
+/// Dresses the player up as the requested character by copying the character
+/// prefab's model, animations and animation controller onto the player entity.
+pub fn equip_character(
+  scene: &mut Scene,
+  level: &Level,
+  player_entity: Entity,
+  character: Option<&str>,
+) {
+  let Some(character_prefab) = level.character(character) else {
+    return;
+  };
+  let model = prefab_component::<ModelComponent>(character_prefab).cloned();
+  let animation = prefab_component::<AnimationComponent>(character_prefab).cloned();
+  let controller = prefab_component::<AnimationControllerComponent>(character_prefab).cloned();
 
-    let mut prefabs = vec![];
-    for (name, prefab) in scene.iter_prefabs() {
-      prefabs.push((name.clone(), prefab.clone()));
+  if let Ok((player_model, player_animation)) =
+    scene.query_one_mut::<(&mut ModelComponent, &mut AnimationComponent)>(player_entity)
+  {
+    if let Some(model) = model {
+      *player_model = model;
     }
+    *player_animation = animation.unwrap_or(AnimationComponent { animations: vec![] });
+  }
 
-    log::info!(
-      "SYNC WORLD WITH {:?}\n{:#?}\n{:#?}\n{:#?}",
-      &player_id,
-      &definitions,
-      &entities,
-      &prefabs
-    );
+  if let Ok(player_controller) =
+    scene.query_one_mut::<&mut AnimationControllerComponent>(player_entity)
+  {
+    *player_controller = controller.unwrap_or(AnimationControllerComponent { states: vec![] });
+  }
+}
+
+/// Sends a player the whole world: the level's config, assets and prefabs and
+/// every entity in the scene. Ghosts are left out, as they're only for the
+/// player racing them, and that player gets theirs sent again afterwards.
+pub fn sync_world(
+  client_sender: &ClientSender<TrustedInput>,
+  scene: &mut Scene,
+  backpack: &Backpack,
+  player_id: &PlayerId,
+) {
+  let level = backpack.get::<Level>().unwrap();
+  let mut definitions = vec![];
 
-    if let Some(config) = &self.config {
-      self.client_sender.send_reliable(
-        *player_id,
-        TrustedInput::Config {
-          config: config.clone(),
-        },
-      );
+  for (id, definition) in level.store.iter_assets() {
+    let packed = AssetPack::pack(definition);
+    definitions.push(packed);
+  }
+
+  let entities_data: Vec<_> = scene
+    .iter()
+    .map(|entity| entity.entity())
+    .collect::<Vec<Entity>>();
+
+  let mut entities = vec![];
+
+  for entity in entities_data {
+    if scene.query_one_mut::<&GhostComponent>(entity).is_ok() {
+      continue;
     }
-    self.client_sender.send_reliable(
-      *player_id,
-      TrustedInput::Assets {
-        assets: definitions,
-        trigger_loading: true,
-      },
-    );
+    let mut prefab = Prefab::pack(scene, entity).unwrap();
+    let is_self = **player_id == **prefab.id;
+    if is_self {
+      prefab.components.push(Box::new(SelfComponent {}));
+    }
+    entities.push(prefab);
+  }
+
+  let mut prefabs = vec![];
+  for (name, prefab) in scene.iter_prefabs() {
+    prefabs.push((name.clone(), prefab.clone()));
+  }
+  for prefab in level.prefabs.catalog() {
+    prefabs.push((prefab.tag.name.clone(), prefab.clone()));
+  }
 
-    self
-      .client_sender
-      .send_reliable(*player_id, TrustedInput::Prefabs { prefabs });
+  log::info!(
+    "SYNC WORLD WITH {:?}\n{:#?}\n{:#?}\n{:#?}",
+    &player_id,
+    &definitions,
+    &entities,
+    &prefabs
+  );
 
-    self
-      .client_sender
-      .send_reliable(*player_id, TrustedInput::Entities { entities });
+  client_sender.send_reliable(
+    *player_id,
+    TrustedInput::Config {
+      config: level.config.clone(),
+    },
+  );
+  client_sender.send_reliable(
+    *player_id,
+    TrustedInput::Assets {
+      assets: definitions,
+      trigger_loading: true,
+    },
+  );
+
+  client_sender.send_reliable(*player_id, TrustedInput::Prefabs { prefabs });
+
+  client_sender.send_reliable(*player_id, TrustedInput::Entities { entities });
+
+  if let Some(ghosts) = backpack.get::<GhostEntities>()
+    && !ghosts.get(player_id).is_empty()
+  {
+    send_entities(client_sender, scene, ghosts.get(player_id), player_id);
   }
 }
 
+/// Sends a player just the given entities, for things spawned mid-race that
+/// don't warrant resending the whole world.
+pub fn send_entities(
+  client_sender: &ClientSender<TrustedInput>,
+  scene: &mut Scene,
+  entities: &[Entity],
+  player_id: &PlayerId,
+) {
+  let entities = entities
+    .iter()
+    .filter_map(|entity| Prefab::pack(scene, *entity).ok())
+    .collect();
+
+  client_sender.send_reliable(*player_id, TrustedInput::Entities { entities });
+}
+
 #[async_trait]
 impl ChannelEvents for NetworkController {
   fn on_session_start(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
     log::info!("Connected to sidecar!!!");
 
-    let gamefile = Gamefile::from_file(&self.download_sender, "arena.lvl");
-
-    self.config = Some(gamefile.config.clone());
-
-    for (id, terrain) in gamefile.scene.terrains {
-      self.store.insert_asset(id, terrain);
-    }
-    for (id, model) in gamefile.scene.models {
-      self.store.insert_asset(id, model);
-    }
-    for (id, trimesh) in gamefile.scene.trimeshes {
-      self.store.insert_asset(id, trimesh);
-    }
-    for (id, background) in gamefile.scene.dynamic_backgrounds {
-      self.store.insert_asset(id, background);
-    }
-    for (id, animation) in gamefile.scene.animations {
-      self.store.insert_asset(id, animation);
-    }
+    let mut rotation = LevelRotation::from_env();
+    let level = Level::load_playable(&self.download_sender, scene, &mut rotation);
+    let recorder = ReplayRecorder::from_env(&level.name);
+    let ghosts = GhostStore::from_env(&level.name);
+    let race = Race::from_env();
+    log::info!("starting a {:?} session", race.mode());
 
-    for (id, prefab) in gamefile.scene.prefabs {
-      match prefab.tag.name.as_str() {
-        "Player" => {
-          log::info!("creating player prefab: {:?}", prefab.tag.name);
-          self.prefabs.insert(ModelNames::Player, prefab.clone());
-        }
-        "Smoke Bomb" => {
-          log::info!("creating smoke bomb prefab: {:?}", prefab.tag.name);
-          self.prefabs.insert(ModelNames::SmokeBomb, prefab.clone());
-        }
-        "Hoverboard" => {
-          log::info!("creating hoverboard prefab: {:?}", prefab.tag.name);
-          self.prefabs.insert(ModelNames::Hoverboard, prefab.clone());
-        }
-        _ => {
-          log::info!("receiving entity {:?}", prefab.tag.name);
-          let entity = scene.create_raw_entity("tmp");
-          scene.create_with_prefab(entity, prefab);
-        }
-      }
-    }
+    backpack.insert(rotation);
+    backpack.insert(level);
+    backpack.insert(Players::default());
+    backpack.insert(race);
+    backpack.insert(recorder);
+    backpack.insert(ghosts);
+    backpack.insert(GhostEntities::default());
+    backpack.insert(Bots::from_env());
   }
 
   fn on_player_joined(
@@ -204,26 +368,11 @@ impl ChannelEvents for NetworkController {
     username: String,
     protocol: Protocol,
   ) {
-    let mut player_prefab: Prefab = self.prefabs.get(&ModelNames::Player).unwrap().clone();
-    log::info!("Player joined! New prefab: {:#?}", &player_prefab);
-
-    *player_prefab.id = PrefabId::with_id(*player_id);
-    scene.create_with_prefab(entity, player_prefab);
-
-    // spawn new hoverboard and reparent to new player
-    let mut hoverboard_prefab: Prefab = self.prefabs.get(&ModelNames::Hoverboard).unwrap().clone();
-    let hoverboard_entity = scene.create_raw_entity("Hoverboard");
-    *hoverboard_prefab.id = PrefabId::new();
-    scene.create_with_prefab(hoverboard_entity, hoverboard_prefab);
-
-    if let parent_component = scene
-      .query_one_mut::<&mut ParentComponent>(hoverboard_entity)
-      .unwrap()
-    {
-      parent_component.id = PrefabId::with_id(*player_id);
+    // nothing is spawned until the client's join message says what to spawn
+    if let Some(players) = backpack.get_mut::<Players>() {
+      players.set_username(player_id, username);
+      players.insert_pending(player_id, entity);
     }
-
-    self.sync_world(scene, &player_id);
   }
 
   fn on_player_left(
@@ -235,6 +384,33 @@ impl ChannelEvents for NetworkController {
     protocol: Protocol,
   ) {
     log::info!("[on player left] Player left {player_id:?}");
-    let _ = scene.despawn(entity);
+
+    // racers respawned by a level change or a rejoin from spectating no
+    // longer live on the entity they joined with
+    let mut spawned = None;
+    if let Some(players) = backpack.get_mut::<Players>() {
+      players.remove_spectator(&player_id);
+      players.remove_pending(&player_id);
+      if let Some(player_entities) = players.remove(&player_id) {
+        let _ = scene.despawn(player_entities.hoverboard);
+        let _ = scene.despawn(player_entities.player);
+        spawned = Some(player_entities.player);
+      }
+    }
+
+    // nobody left to race, so the recording so far is as complete as it gets
+    let everyone_left = backpack
+      .get::<Players>()
+      .map_or(false, |players| players.connected_ids().is_empty());
+    if everyone_left {
+      let level = backpack.get::<Level>().unwrap().name.clone();
+      if let Some(recorder) = backpack.get_mut::<ReplayRecorder>() {
+        recorder.finish(&level);
+      }
+    }
+
+    if spawned != Some(entity) {
+      let _ = scene.despawn(entity);
+    }
   }
 }
@@ -0,0 +1,152 @@
+use engine::{networking::connection::PlayerId, Entity};
+use std::collections::{HashMap, HashSet};
+
+use crate::shared::loadout::Loadout;
+
+#[derive(Debug, Clone)]
+pub struct PlayerEntities {
+  pub player: Entity,
+  pub hoverboard: Entity,
+  pub loadout: Loadout,
+  /// The spawn point the player started the race from.
+  pub slot: usize,
+}
+
+/// Connected players and the entities spawned for them, so the session can
+/// tear them down and respawn them when the level changes. Pending players
+/// have connected but not yet sent the join message saying what to spawn.
+/// Spectators are connected but have nothing spawned. Bots race like everyone else but have
+/// no connection. Loadouts outlive the connection, so a player that rejoins
+/// gets the same board and avatar.
+#[derive(Debug, Default)]
+pub struct Players {
+  entities: HashMap<PlayerId, PlayerEntities>,
+  pending: HashMap<PlayerId, Entity>,
+  spectators: HashSet<PlayerId>,
+  bots: HashSet<PlayerId>,
+  loadouts: HashMap<PlayerId, Loadout>,
+  usernames: HashMap<PlayerId, String>,
+}
+
+impl Players {
+  pub fn insert(&mut self, player_id: PlayerId, entities: PlayerEntities) {
+    self.spectators.remove(&player_id);
+    self.loadouts.insert(player_id, entities.loadout.clone());
+    self.entities.insert(player_id, entities);
+  }
+
+  /// Holds on to the entity a player joined with until their join message
+  /// arrives.
+  pub fn insert_pending(&mut self, player_id: PlayerId, entity: Entity) {
+    self.pending.insert(player_id, entity);
+  }
+
+  pub fn remove_pending(&mut self, player_id: &PlayerId) -> Option<Entity> {
+    self.pending.remove(player_id)
+  }
+
+  pub fn pending(&self) -> Vec<(PlayerId, Entity)> {
+    self
+      .pending
+      .iter()
+      .map(|(player_id, entity)| (*player_id, *entity))
+      .collect()
+  }
+
+  pub fn insert_spectator(&mut self, player_id: PlayerId) {
+    self.spectators.insert(player_id);
+  }
+
+  pub fn remove_spectator(&mut self, player_id: &PlayerId) -> bool {
+    self.spectators.remove(player_id)
+  }
+
+  pub fn spectator_ids(&self) -> Vec<PlayerId> {
+    self.spectators.iter().copied().collect()
+  }
+
+  pub fn insert_bot(&mut self, player_id: PlayerId, entities: PlayerEntities) {
+    self.bots.insert(player_id);
+    self.insert(player_id, entities);
+  }
+
+  pub fn remove_bot(&mut self, player_id: &PlayerId) -> Option<PlayerEntities> {
+    self.bots.remove(player_id);
+    self.loadouts.remove(player_id);
+    self.usernames.remove(player_id);
+    self.remove(player_id)
+  }
+
+  pub fn is_bot(&self, player_id: &PlayerId) -> bool {
+    self.bots.contains(player_id)
+  }
+
+  pub fn bot_ids(&self) -> Vec<PlayerId> {
+    self.bots.iter().copied().collect()
+  }
+
+  /// Racers and spectators alike, bots aside. Pending players are left out
+  /// until they have been sent the world.
+  pub fn connected_ids(&self) -> Vec<PlayerId> {
+    self
+      .entities
+      .keys()
+      .filter(|player_id| !self.bots.contains(player_id))
+      .chain(self.spectators.iter())
+      .copied()
+      .collect()
+  }
+
+  pub fn loadout(&self, player_id: &PlayerId) -> Loadout {
+    self.loadouts.get(player_id).cloned().unwrap_or_default()
+  }
+
+  pub fn set_loadout(&mut self, player_id: PlayerId, loadout: Loadout) {
+    self.loadouts.insert(player_id, loadout);
+  }
+
+  pub fn username(&self, player_id: &PlayerId) -> &str {
+    self
+      .usernames
+      .get(player_id)
+      .map(String::as_str)
+      .unwrap_or_default()
+  }
+
+  pub fn set_username(&mut self, player_id: PlayerId, username: String) {
+    self.usernames.insert(player_id, username);
+  }
+
+  pub fn remove(&mut self, player_id: &PlayerId) -> Option<PlayerEntities> {
+    self.entities.remove(player_id)
+  }
+
+  pub fn get(&self, player_id: &PlayerId) -> Option<&PlayerEntities> {
+    self.entities.get(player_id)
+  }
+
+  pub fn get_mut(&mut self, player_id: &PlayerId) -> Option<&mut PlayerEntities> {
+    self.entities.get_mut(player_id)
+  }
+
+  /// Players currently racing.
+  pub fn ids(&self) -> Vec<PlayerId> {
+    self.entities.keys().copied().collect()
+  }
+
+  /// The first spawn slot nobody in the race has taken, so a player joining
+  /// after someone left doesn't start on top of another racer.
+  pub fn free_slot(&self) -> usize {
+    (0..)
+      .find(|slot| !self.entities.values().any(|player| player.slot == *slot))
+      .unwrap()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entities.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entities.is_empty()
+  }
+}
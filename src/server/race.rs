@@ -0,0 +1,482 @@
+use engine::networking::connection::PlayerId;
+use nalgebra::Vector3;
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::server::level::Checkpoint;
+
+/// How the session is played, picked with `--mode <mode>` or the `GAME_MODE`
+/// env var.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+  /// Everyone races everyone, one time boxed round per level.
+  #[default]
+  Race,
+  /// A single player laps the track against their ghosts, for as long as they
+  /// like.
+  TimeTrial,
+}
+
+impl GameMode {
+  pub fn from_env() -> Self {
+    let mut mode = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+      if arg == "--mode" {
+        mode = args.next();
+      }
+    }
+
+    let mode = mode.or_else(|| dotenv::var("GAME_MODE").ok());
+    match mode.as_deref() {
+      None | Some("race") => Self::Race,
+      Some("time-trial") => Self::TimeTrial,
+      Some(mode) => {
+        log::warn!("unknown game mode {:?}, falling back to race", mode);
+        Self::Race
+      }
+    }
+  }
+}
+
+/// How far a racer has made it around the track.
+#[derive(Debug, Clone)]
+pub struct Progress {
+  /// Laps completed.
+  pub lap: u32,
+  /// Index into the level's checkpoints of the one to pass next.
+  pub next_checkpoint: usize,
+  /// Race time the current lap started at.
+  pub lap_started: f32,
+  /// Lap time at each checkpoint passed on the current lap.
+  pub splits: Vec<f32>,
+  pub last_lap: Option<f32>,
+  pub best_lap: Option<f32>,
+  /// Place the racer finished in, once they've done all the laps.
+  pub finished: Option<u32>,
+  /// Times the racer drove into another board.
+  pub rams: u32,
+}
+
+impl Progress {
+  fn new(time: f32, checkpoints: usize) -> Self {
+    Self {
+      lap: 0,
+      // racers start on the start/finish line, so they head for the next one
+      next_checkpoint: 1 % checkpoints.max(1),
+      lap_started: time,
+      splits: vec![],
+      last_lap: None,
+      best_lap: None,
+      finished: None,
+      rams: 0,
+    }
+  }
+}
+
+/// Something that happened during the current tick, for other systems to
+/// react to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RaceEvent {
+  Checkpoint {
+    player_id: PlayerId,
+    checkpoint: u32,
+    lap: u32,
+    split: f32,
+  },
+  LapStarted {
+    player_id: PlayerId,
+  },
+  LapCompleted {
+    player_id: PlayerId,
+    lap: u32,
+    time: f32,
+    splits: Vec<f32>,
+  },
+  Finished {
+    player_id: PlayerId,
+    place: u32,
+  },
+}
+
+/// Clock and lap progress for the race on the current level, restarted
+/// whenever the level changes. Players that join after `LATE_JOIN_GRACE`
+/// seconds (15 by default) spectate until the next race. Racers finish after
+/// `RACE_LAPS` laps (3 by default, 0 never finishes). Time trials take a
+/// single racer, whenever they join, and never finish.
+pub struct Race {
+  mode: GameMode,
+  elapsed: f32,
+  late_join_grace: f32,
+  laps: u32,
+  progress: HashMap<PlayerId, Progress>,
+  /// Racers that finished so far, counting the ones that left since, so
+  /// places already handed out aren't given out again.
+  finishers: u32,
+  events: Vec<RaceEvent>,
+}
+
+impl Race {
+  pub fn from_env() -> Self {
+    let late_join_grace = dotenv::var("LATE_JOIN_GRACE")
+      .ok()
+      .and_then(|grace| grace.parse::<f32>().ok())
+      .unwrap_or(15.0);
+    let laps = dotenv::var("RACE_LAPS")
+      .ok()
+      .and_then(|laps| laps.parse::<u32>().ok())
+      .unwrap_or(3);
+
+    Self {
+      mode: GameMode::from_env(),
+      elapsed: 0.0,
+      late_join_grace,
+      laps,
+      progress: HashMap::new(),
+      finishers: 0,
+      events: vec![],
+    }
+  }
+
+  pub fn restart(&mut self) {
+    self.elapsed = 0.0;
+    self.progress.clear();
+    self.finishers = 0;
+    self.events.clear();
+  }
+
+  pub fn tick(&mut self, delta_time: f32) {
+    self.elapsed += delta_time;
+    self.events.clear();
+  }
+
+  pub fn elapsed(&self) -> f32 {
+    self.elapsed
+  }
+
+  pub fn mode(&self) -> GameMode {
+    self.mode
+  }
+
+  /// Whether another player can join the race, given how many are already
+  /// racing.
+  pub fn accepts_racers(&self, racers: usize) -> bool {
+    match self.mode {
+      GameMode::Race => self.elapsed <= self.late_join_grace,
+      GameMode::TimeTrial => racers == 0,
+    }
+  }
+
+  /// Laps to finish the race, or `None` when it doesn't end.
+  pub fn laps(&self) -> Option<u32> {
+    match self.mode {
+      GameMode::Race if self.laps > 0 => Some(self.laps),
+      _ => None,
+    }
+  }
+
+  /// Whether someone has finished and so has every one of `racers`.
+  pub fn is_over(&self, racers: &[PlayerId]) -> bool {
+    self.finishers > 0 && racers.iter().all(|player_id| self.has_finished(player_id))
+  }
+
+  /// Racers that finished so far, including the ones that left since.
+  pub fn finishers(&self) -> u32 {
+    self.finishers
+  }
+
+  pub fn progress(&self, player_id: &PlayerId) -> Option<&Progress> {
+    self.progress.get(player_id)
+  }
+
+  /// Events from the current tick.
+  pub fn events(&self) -> &[RaceEvent] {
+    &self.events
+  }
+
+  /// Checks whether the racer reached their next checkpoint. Racers are
+  /// tracked from the first time they're seen here, on levels with at least
+  /// the start line and one more checkpoint.
+  pub fn update_racer(
+    &mut self,
+    player_id: PlayerId,
+    position: &Vector3<f32>,
+    checkpoints: &[Checkpoint],
+  ) {
+    if checkpoints.len() < 2 {
+      return;
+    }
+
+    let elapsed = self.elapsed;
+    let laps = self.laps();
+    let progress = match self.progress.entry(player_id) {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => {
+        self.events.push(RaceEvent::LapStarted { player_id });
+        entry.insert(Progress::new(elapsed, checkpoints.len()))
+      }
+    };
+    if progress.finished.is_some() {
+      return;
+    }
+
+    let Some(checkpoint) = checkpoints.get(progress.next_checkpoint) else {
+      return;
+    };
+    if (checkpoint.position - position).norm() > checkpoint.radius {
+      return;
+    }
+
+    let time = elapsed - progress.lap_started;
+    progress.next_checkpoint = (progress.next_checkpoint + 1) % checkpoints.len();
+    if progress.next_checkpoint != 1 % checkpoints.len() {
+      progress.splits.push(time);
+      self.events.push(RaceEvent::Checkpoint {
+        player_id,
+        checkpoint: checkpoint.index,
+        lap: progress.lap,
+        split: time,
+      });
+      return;
+    }
+
+    let splits = std::mem::take(&mut progress.splits);
+    progress.lap += 1;
+    progress.lap_started = elapsed;
+    progress.last_lap = Some(time);
+    progress.best_lap = Some(progress.best_lap.map_or(time, |best| best.min(time)));
+
+    self.events.push(RaceEvent::LapCompleted {
+      player_id,
+      lap: progress.lap,
+      time,
+      splits,
+    });
+
+    if laps.is_some_and(|laps| progress.lap >= laps) {
+      self.finishers += 1;
+      let place = self.finishers;
+      progress.finished = Some(place);
+      self.events.push(RaceEvent::Finished { player_id, place });
+    } else {
+      self.events.push(RaceEvent::LapStarted { player_id });
+    }
+  }
+
+  /// Throws the racer's current lap away and starts a fresh one, for when
+  /// they've been put back on the start line.
+  pub fn restart_lap(&mut self, player_id: PlayerId, checkpoints: usize) {
+    let Some(progress) = self.progress.get_mut(&player_id) else {
+      return;
+    };
+
+    progress.next_checkpoint = 1 % checkpoints.max(1);
+    progress.lap_started = self.elapsed;
+    progress.splits.clear();
+    self.events.push(RaceEvent::LapStarted { player_id });
+  }
+
+  /// Counts a bump the racer caused.
+  pub fn count_ram(&mut self, player_id: &PlayerId) {
+    if let Some(progress) = self.progress.get_mut(player_id) {
+      progress.rams += 1;
+    }
+  }
+
+  /// Stops tracking players that are no longer racing. Finished racers are
+  /// kept, so they aren't let back into the race they already finished.
+  pub fn retain_racers(&mut self, racers: &[PlayerId]) {
+    self
+      .progress
+      .retain(|player_id, progress| progress.finished.is_some() || racers.contains(player_id));
+  }
+
+  /// Whether the player finished this race.
+  pub fn has_finished(&self, player_id: &PlayerId) -> bool {
+    self
+      .progress
+      .get(player_id)
+      .is_some_and(|progress| progress.finished.is_some())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use uuid::Uuid;
+
+  fn race(laps: u32) -> Race {
+    Race {
+      mode: GameMode::Race,
+      elapsed: 0.0,
+      late_join_grace: 15.0,
+      laps,
+      progress: HashMap::new(),
+      finishers: 0,
+      events: vec![],
+    }
+  }
+
+  fn checkpoints() -> Vec<Checkpoint> {
+    [[0.0, 0.0, 0.0], [50.0, 0.0, 0.0], [50.0, 0.0, 50.0]]
+      .iter()
+      .enumerate()
+      .map(|(index, position)| Checkpoint {
+        index: index as u32,
+        position: Vector3::from(*position),
+        radius: 5.0,
+        distance: None,
+      })
+      .collect()
+  }
+
+  /// Drives `player_id` through every checkpoint after the start line and
+  /// back over it, a second per checkpoint.
+  fn lap(race: &mut Race, player_id: PlayerId, checkpoints: &[Checkpoint]) {
+    for index in (1..checkpoints.len()).chain([0]) {
+      race.tick(1.0);
+      race.update_racer(player_id, &checkpoints[index].position, checkpoints);
+    }
+  }
+
+  #[test]
+  fn racers_are_tracked_from_the_start_line() {
+    let checkpoints = checkpoints();
+    let mut race = race(3);
+    let player_id = PlayerId::from(Uuid::new_v4());
+
+    race.update_racer(player_id, &checkpoints[0].position, &checkpoints);
+    let progress = race.progress(&player_id).unwrap();
+    assert_eq!(progress.lap, 0);
+    assert_eq!(progress.next_checkpoint, 1);
+    assert_eq!(race.events(), &[RaceEvent::LapStarted { player_id }]);
+  }
+
+  #[test]
+  fn checkpoints_only_count_in_order_and_in_range() {
+    let checkpoints = checkpoints();
+    let mut race = race(3);
+    let player_id = PlayerId::from(Uuid::new_v4());
+    race.update_racer(player_id, &checkpoints[0].position, &checkpoints);
+
+    race.tick(1.0);
+    race.update_racer(player_id, &checkpoints[2].position, &checkpoints);
+    race.update_racer(player_id, &Vector3::new(40.0, 0.0, 0.0), &checkpoints);
+    assert!(race.events().is_empty());
+
+    race.tick(1.0);
+    race.update_racer(player_id, &Vector3::new(48.0, 0.0, 2.0), &checkpoints);
+    assert_eq!(
+      race.events(),
+      &[RaceEvent::Checkpoint {
+        player_id,
+        checkpoint: 1,
+        lap: 0,
+        split: 2.0,
+      }]
+    );
+    assert_eq!(race.progress(&player_id).unwrap().next_checkpoint, 2);
+  }
+
+  #[test]
+  fn crossing_the_start_line_completes_a_lap() {
+    let checkpoints = checkpoints();
+    let mut race = race(3);
+    let player_id = PlayerId::from(Uuid::new_v4());
+    race.update_racer(player_id, &checkpoints[0].position, &checkpoints);
+
+    lap(&mut race, player_id, &checkpoints);
+    assert_eq!(
+      race.events(),
+      &[
+        RaceEvent::LapCompleted {
+          player_id,
+          lap: 1,
+          time: 3.0,
+          splits: vec![1.0, 2.0],
+        },
+        RaceEvent::LapStarted { player_id },
+      ]
+    );
+
+    let progress = race.progress(&player_id).unwrap();
+    assert_eq!(progress.lap, 1);
+    assert_eq!(progress.last_lap, Some(3.0));
+    assert_eq!(progress.best_lap, Some(3.0));
+    assert!(progress.splits.is_empty());
+    assert_eq!(progress.next_checkpoint, 1);
+  }
+
+  #[test]
+  fn finish_places_are_never_handed_out_twice() {
+    let checkpoints = checkpoints();
+    let mut race = race(1);
+    let racers: Vec<PlayerId> = (0..3).map(|_| PlayerId::from(Uuid::new_v4())).collect();
+    for player_id in &racers {
+      race.update_racer(*player_id, &checkpoints[0].position, &checkpoints);
+    }
+
+    lap(&mut race, racers[0], &checkpoints);
+    assert_eq!(race.progress(&racers[0]).unwrap().finished, Some(1));
+    assert!(race.events().contains(&RaceEvent::Finished {
+      player_id: racers[0],
+      place: 1,
+    }));
+
+    // the winner leaving doesn't free up first place
+    race.retain_racers(&racers[1..]);
+    assert!(race.has_finished(&racers[0]));
+    lap(&mut race, racers[1], &checkpoints);
+    assert_eq!(race.progress(&racers[1]).unwrap().finished, Some(2));
+
+    // racers still going that leave are forgotten
+    race.retain_racers(&racers[..2]);
+    assert!(race.progress(&racers[2]).is_none());
+
+    // finished racers stay put
+    race.tick(1.0);
+    race.update_racer(racers[1], &checkpoints[1].position, &checkpoints);
+    assert!(race.events().is_empty());
+    assert_eq!(race.progress(&racers[1]).unwrap().next_checkpoint, 1);
+  }
+
+  #[test]
+  fn races_are_over_once_every_racer_finished() {
+    let checkpoints = checkpoints();
+    let mut race = race(1);
+    let racers: Vec<PlayerId> = (0..2).map(|_| PlayerId::from(Uuid::new_v4())).collect();
+    assert!(!race.is_over(&[]));
+    for player_id in &racers {
+      race.update_racer(*player_id, &checkpoints[0].position, &checkpoints);
+    }
+
+    lap(&mut race, racers[0], &checkpoints);
+    assert!(!race.is_over(&racers));
+    assert!(race.is_over(&racers[..1]));
+
+    lap(&mut race, racers[1], &checkpoints);
+    assert!(race.is_over(&racers));
+  }
+
+  #[test]
+  fn levels_without_checkpoints_track_nobody() {
+    let mut race = race(3);
+    let player_id = PlayerId::from(Uuid::new_v4());
+    race.update_racer(player_id, &Vector3::zeros(), &[]);
+    assert!(race.progress(&player_id).is_none());
+    assert!(race.events().is_empty());
+  }
+
+  #[test]
+  fn a_single_checkpoint_never_completes_a_lap() {
+    let checkpoints = &checkpoints()[..1];
+    let mut race = race(3);
+    let player_id = PlayerId::from(Uuid::new_v4());
+    for _ in 0..10 {
+      race.tick(0.1);
+      race.update_racer(player_id, &checkpoints[0].position, checkpoints);
+      assert!(race.events().is_empty());
+    }
+    assert!(race.progress(&player_id).is_none());
+  }
+}
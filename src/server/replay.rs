@@ -0,0 +1,230 @@
+use engine::networking::connection::PlayerId;
+use nalgebra::Vector3;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::shared::loadout::Loadout;
+use crate::shared::replay::{
+  Replay, ReplayEvent, ReplayEventKind, ReplayFrame, ReplayRacer, ReplayTransform,
+};
+
+/// Seconds between recorded frames. Playback interpolates between them.
+const FRAME_INTERVAL: f32 = 1.0 / 20.0;
+/// Longest stretch kept in one file, for races that don't end on their own.
+const MAX_DURATION: f32 = 15.0 * 60.0;
+
+/// Records the race on the current level into a `Replay`, and writes it to
+/// `REPLAY_DIR` (`replays` by default) when the race ends, or every
+/// `MAX_DURATION` seconds for races that don't. Setting `REPLAY_DIR` to an
+/// empty string turns recording off.
+pub struct ReplayRecorder {
+  replay: Replay,
+  racers: HashMap<PlayerId, u16>,
+  directory: Option<PathBuf>,
+  /// Race time the replay starts at, which frames and events are relative to.
+  started: Option<f32>,
+}
+
+impl ReplayRecorder {
+  pub fn from_env(level: &str) -> Self {
+    let directory = match dotenv::var("REPLAY_DIR") {
+      Ok(directory) if directory.is_empty() => None,
+      Ok(directory) => Some(PathBuf::from(directory)),
+      Err(_) => Some(PathBuf::from("replays")),
+    };
+
+    Self {
+      replay: Replay::new(level),
+      racers: HashMap::new(),
+      directory,
+      started: None,
+    }
+  }
+
+  pub fn is_recording(&self) -> bool {
+    self.directory.is_some()
+  }
+
+  /// The racer's index in the replay, adding them the first time they show
+  /// up.
+  pub fn racer(&mut self, player_id: PlayerId, username: &str, loadout: &Loadout) -> u16 {
+    if let Some(index) = self.racers.get(&player_id) {
+      return *index;
+    }
+
+    let index = self.replay.racers.len() as u16;
+    self.replay.racers.push(ReplayRacer {
+      id: *player_id,
+      username: username.to_string(),
+      loadout: loadout.clone(),
+    });
+    self.racers.insert(player_id, index);
+    index
+  }
+
+  /// Whether a frame for race time `time` would be kept. Frames in between
+  /// are dropped.
+  pub fn is_frame_due(&self, time: f32) -> bool {
+    let time = time - self.started.unwrap_or(time);
+    self
+      .replay
+      .frames
+      .last()
+      .map_or(true, |frame| time - frame.time >= FRAME_INTERVAL)
+  }
+
+  pub fn record_frame(&mut self, time: f32, transforms: Vec<(u16, Vector3<f32>, Vector3<f32>)>) {
+    if !self.is_frame_due(time) {
+      return;
+    }
+
+    let started = *self.started.get_or_insert(time);
+    let transforms = transforms
+      .into_iter()
+      .map(|(racer, translation, rotation)| ReplayTransform::new(racer, &translation, &rotation))
+      .collect();
+
+    self.replay.frames.push(ReplayFrame {
+      time: time - started,
+      transforms,
+    });
+  }
+
+  /// Events for players that never raced in this replay are dropped.
+  pub fn record_event(&mut self, time: f32, player_id: &PlayerId, kind: ReplayEventKind) {
+    let (Some(racer), Some(started)) = (self.racers.get(player_id), self.started) else {
+      return;
+    };
+
+    self.replay.events.push(ReplayEvent {
+      time: time - started,
+      racer: *racer,
+      kind,
+    });
+  }
+
+  /// Whether anything was recorded since the last `finish`.
+  pub fn has_frames(&self) -> bool {
+    !self.replay.frames.is_empty()
+  }
+
+  /// Whether the replay reached `MAX_DURATION` and should be written out.
+  pub fn is_full(&self) -> bool {
+    self.replay.duration() >= MAX_DURATION
+  }
+
+  /// Writes the race recorded so far and carries on recording the same level
+  /// into a new replay.
+  pub fn flush(&mut self) {
+    let level = self.replay.level.clone();
+    self.finish(&level);
+  }
+
+  /// Writes the race recorded so far and starts an empty one on `next_level`.
+  pub fn finish(&mut self, next_level: &str) {
+    let replay = std::mem::replace(&mut self.replay, Replay::new(next_level));
+    self.racers.clear();
+    self.started = None;
+
+    let Some(directory) = &self.directory else {
+      return;
+    };
+    if replay.frames.is_empty() {
+      return;
+    }
+
+    let level = replay.level.trim_end_matches(".lvl");
+    let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    let path = directory.join(format!("{level}-{timestamp}.replay"));
+
+    let result =
+      std::fs::create_dir_all(directory).and_then(|_| std::fs::write(&path, replay.encode()));
+    match result {
+      Ok(()) => log::info!("saved replay {:?}", path),
+      Err(error) => log::error!("could not save replay {:?}: {}", path, error),
+    }
+  }
+}
+
+/// Plays the file given with `--replay <file>` headlessly, if any. Returns
+/// whether it did, in which case the server shouldn't start.
+pub fn play_from_args() -> bool {
+  let mut path = None;
+  let mut speed = 1.0;
+  let mut from = 0.0;
+
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--replay" => path = args.next(),
+      "--speed" => {
+        speed = args
+          .next()
+          .and_then(|speed| speed.parse().ok())
+          .unwrap_or(speed)
+      }
+      "--from" => {
+        from = args
+          .next()
+          .and_then(|from| from.parse().ok())
+          .unwrap_or(from)
+      }
+      _ => {}
+    }
+  }
+
+  let Some(path) = path else {
+    return false;
+  };
+  play_headless(&path, speed, from);
+  true
+}
+
+/// Headless playback for `--replay <file>`: plays the replay back in real
+/// time without a scene, scaled by `--speed` and starting at `--from`
+/// seconds, logging every event and where everyone ended up.
+pub fn play_headless(path: &str, speed: f32, from: f32) {
+  use crate::shared::replay::ReplayPlayback;
+
+  const STEP: f32 = 1.0 / 60.0;
+
+  let replay = match std::fs::read(path)
+    .map_err(|error| error.to_string())
+    .and_then(|bytes| Replay::decode(&bytes))
+  {
+    Ok(replay) => replay,
+    Err(error) => {
+      log::error!("could not load replay {:?}: {}", path, error);
+      return;
+    }
+  };
+  if speed <= 0.0 {
+    log::error!("replay speed must be positive, got {}", speed);
+    return;
+  }
+
+  log::info!(
+    "replay of {:?}: {} racers, {} frames, {:.1}s",
+    replay.level,
+    replay.racers.len(),
+    replay.frames.len(),
+    replay.duration()
+  );
+
+  let mut playback = ReplayPlayback::new(replay);
+  playback.set_speed(speed);
+  playback.seek(from);
+
+  while !playback.is_finished() {
+    for event in playback.advance(STEP) {
+      let racer = &playback.replay.racers[event.racer as usize];
+      log::info!("{:>8.2}s {:?} {:?}", event.time, racer.username, event.kind);
+    }
+    std::thread::sleep(std::time::Duration::from_secs_f32(STEP));
+  }
+
+  for (racer, translation, _) in playback.replay.sample(playback.time) {
+    let racer = &playback.replay.racers[racer as usize];
+    log::info!("{:?} ended at {:?}", racer.username, translation);
+  }
+}
@@ -0,0 +1,90 @@
+use crate::server::bots::{racer_input, Bots};
+use crate::server::players::Players;
+use crate::shared::components::BoostMeterComponent;
+use crate::shared::input::{Actions, PlayerInput};
+
+use engine::application::scene::{Scene, TransformComponent};
+use engine::networking::connection::PlayerId;
+use engine::systems::{input::InputsReader, Backpack, Initializable, Inventory, System};
+use engine::utils::units::Time;
+use nalgebra::Vector3;
+use std::collections::HashMap;
+
+/// Below this many units per second a board isn't drifting, just sitting
+/// there with the brake held.
+const MIN_DRIFT_SPEED: f32 = 1.0;
+
+/// Keeps every racer's `BoostMeterComponent` up to date: drifting fills it,
+/// and holding `Actions::Boost` spends it for as long as there's any left.
+/// The meter lives on the server so it can't be topped up by a client, and
+/// replicates to the racer's HUD and board like any other component.
+pub struct BoostMeterSystem {
+  inputs: InputsReader<PlayerInput>,
+  last_positions: HashMap<PlayerId, Vector3<f32>>,
+}
+
+impl Initializable for BoostMeterSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
+
+    Self {
+      inputs,
+      last_positions: HashMap::new(),
+    }
+  }
+}
+
+impl System for BoostMeterSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    let Some(players) = backpack.get::<Players>() else {
+      return;
+    };
+    let bots = backpack.get::<Bots>();
+
+    let racers = players.ids();
+    self
+      .last_positions
+      .retain(|player_id, _| racers.contains(player_id));
+
+    for player_id in racers {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      let input = racer_input(&self.inputs, bots, &player_id);
+
+      let Ok((transform, meter)) =
+        scene.query_one_mut::<(&TransformComponent, &mut BoostMeterComponent)>(player.player)
+      else {
+        continue;
+      };
+
+      let position = transform.translation;
+      let speed = self
+        .last_positions
+        .insert(player_id, position)
+        .map_or(0.0, |last| {
+          (position - last).norm() / delta_time.max(f32::EPSILON)
+        });
+
+      let (drifting, boosting) = input.map_or((false, false), |input| {
+        (
+          input.actions.contains(&Actions::Brake) && input.direction_vector.x != 0.0,
+          input.actions.contains(&Actions::Boost),
+        )
+      });
+
+      if drifting && speed > MIN_DRIFT_SPEED {
+        meter.fill(meter.drift_fill * delta_time);
+      }
+
+      let active = boosting && meter.amount > 0.0;
+      if active {
+        meter.fill(-meter.drain * delta_time);
+      }
+      if meter.active != active {
+        meter.active = active;
+      }
+    }
+  }
+}
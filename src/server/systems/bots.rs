@@ -0,0 +1,290 @@
+use crate::server::bots::{BotView, Bots};
+use crate::server::level::Level;
+use crate::server::network_controller::{send_entities, spawn_player};
+use crate::server::players::{PlayerEntities, Players};
+use crate::server::race::{GameMode, Race};
+use crate::shared::components::{
+  Ability, BoostMeterComponent, BumpComponent, ItemComponent, PlayerMovementComponent, Role,
+  WallContactComponent,
+};
+use crate::shared::loadout::Loadout;
+use crate::shared::movement;
+use crate::shared::rail::GrindRails;
+
+use engine::application::components::PhysicsComponent;
+use engine::application::{
+  input::TrustedInput,
+  scene::{Scene, TransformComponent},
+};
+use engine::networking::connection::PlayerId;
+use engine::systems::{
+  network::ClientSender, physics::PhysicsController, Backpack, Initializable, Inventory, System,
+};
+use engine::utils::units::Time;
+use engine::Entity;
+use nalgebra::Vector3;
+use rand::seq::IteratorRandom;
+use uuid::Uuid;
+
+/// Tops the race up with bots until there are `Bots::count` racers, and
+/// drives them through the same movement code as the players' boards. Bots
+/// only join while the race still takes racers, make way for players that
+/// join after them, and leave once the last player does.
+pub struct BotSystem {
+  client_sender: ClientSender<TrustedInput>,
+  physics_controller: PhysicsController,
+  running_time: f32,
+}
+
+impl Initializable for BotSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let client_sender = inventory.get::<ClientSender<TrustedInput>>().clone();
+    let physics_controller = inventory.get::<PhysicsController>().clone();
+
+    Self {
+      client_sender,
+      physics_controller,
+      running_time: 0.0,
+    }
+  }
+}
+
+impl System for BotSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    self.running_time += delta_time;
+
+    let added = self.fill(scene, backpack);
+    self.drive(scene, backpack, delta_time);
+
+    // bots that left are despawned on every client already
+    if !added.is_empty() {
+      for player_id in backpack.get::<Players>().unwrap().connected_ids() {
+        send_entities(&self.client_sender, scene, &added, &player_id);
+      }
+    }
+  }
+}
+
+impl BotSystem {
+  /// Adds or removes bots to match the number of players racing. Returns the
+  /// entities of the bots added.
+  fn fill(&mut self, scene: &mut Scene, backpack: &mut Backpack) -> Vec<Entity> {
+    let (Some(players), Some(race), Some(bots)) = (
+      backpack.get::<Players>(),
+      backpack.get::<Race>(),
+      backpack.get::<Bots>(),
+    ) else {
+      return vec![];
+    };
+
+    let bot_ids = players.bot_ids();
+    let humans = players.len() - bot_ids.len();
+    let wanted = if race.mode() == GameMode::TimeTrial || players.connected_ids().is_empty() {
+      0
+    } else {
+      bots.count.saturating_sub(humans)
+    };
+
+    if bot_ids.len() > wanted {
+      for player_id in &bot_ids[wanted..] {
+        self.remove_bot(scene, backpack, player_id);
+      }
+      return vec![];
+    }
+
+    let mut added = vec![];
+    while backpack.get::<Players>().unwrap().bot_ids().len() < wanted {
+      let racers = backpack.get::<Players>().unwrap().len();
+      if !backpack.get::<Race>().unwrap().accepts_racers(racers) {
+        break;
+      }
+      let Some(player_entities) = self.add_bot(scene, backpack) else {
+        break;
+      };
+      added.extend([player_entities.player, player_entities.hoverboard]);
+    }
+    added
+  }
+
+  /// Spawns a bot into the race, unless the level has nothing to spawn it
+  /// as.
+  fn add_bot(&mut self, scene: &mut Scene, backpack: &mut Backpack) -> Option<PlayerEntities> {
+    let player_id = PlayerId::from(Uuid::new_v4());
+    let level = backpack.get::<Level>().unwrap();
+    let players = backpack.get::<Players>().unwrap();
+    let slot = players.free_slot();
+    let username = format!("Bot {}", players.bot_ids().len() + 1);
+
+    // bots pick a random board and character from the level's catalog
+    let mut rng = rand::thread_rng();
+    let loadout = Loadout {
+      board: level
+        .prefabs
+        .iter(Role::Board)
+        .choose(&mut rng)
+        .map(|prefab| prefab.tag.name.clone()),
+      avatar: level
+        .prefabs
+        .iter(Role::Character)
+        .choose(&mut rng)
+        .map(|prefab| prefab.tag.name.clone()),
+      spectate: false,
+    };
+
+    log::info!("{} joins the race", username);
+
+    let entity = scene.create_raw_entity("Bot");
+    let Some(player_entities) =
+      spawn_player(scene, level, entity, player_id, slot, loadout.clone())
+    else {
+      let _ = scene.despawn(entity);
+      return None;
+    };
+
+    if let Some(players) = backpack.get_mut::<Players>() {
+      players.set_username(player_id, username);
+      players.set_loadout(player_id, loadout);
+      players.insert_bot(player_id, player_entities.clone());
+    }
+    if let Some(bots) = backpack.get_mut::<Bots>() {
+      bots.insert(player_id);
+    }
+    Some(player_entities)
+  }
+
+  fn remove_bot(&mut self, scene: &mut Scene, backpack: &mut Backpack, player_id: &PlayerId) {
+    log::info!(
+      "{} leaves the race",
+      backpack.get::<Players>().unwrap().username(player_id)
+    );
+
+    if let Some(player_entities) = backpack.get_mut::<Players>().unwrap().remove_bot(player_id) {
+      let _ = scene.despawn(player_entities.player);
+      let _ = scene.despawn(player_entities.hoverboard);
+    }
+    if let Some(bots) = backpack.get_mut::<Bots>() {
+      bots.remove(player_id);
+    }
+  }
+
+  fn drive(&mut self, scene: &mut Scene, backpack: &mut Backpack, delta_time: f32) {
+    let (Some(players), Some(race), Some(level)) = (
+      backpack.get::<Players>(),
+      backpack.get::<Race>(),
+      backpack.get::<Level>(),
+    ) else {
+      return;
+    };
+
+    let mut racers = vec![];
+    for player_id in players.ids() {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      if let Ok(transform) = scene.query_one_mut::<&TransformComponent>(player.player) {
+        racers.push((player_id, player.player, transform.translation));
+      }
+    }
+
+    let checkpoints = &level.checkpoints;
+    let mut views = vec![];
+    for (player_id, entity, position) in &racers {
+      if !players.is_bot(player_id) {
+        continue;
+      }
+
+      let (next_checkpoint, following_checkpoint) = match race.progress(player_id) {
+        Some(progress) if !checkpoints.is_empty() => {
+          let next = progress.next_checkpoint % checkpoints.len();
+          (
+            Some(checkpoints[next].position),
+            Some(checkpoints[(next + 1) % checkpoints.len()].position),
+          )
+        }
+        _ => (None, None),
+      };
+      let others: Vec<Vector3<f32>> = racers
+        .iter()
+        .filter(|(other_id, ..)| other_id != player_id)
+        .map(|(_, _, position)| *position)
+        .collect();
+
+      views.push((
+        *player_id,
+        *entity,
+        *position,
+        next_checkpoint,
+        following_checkpoint,
+        others,
+      ));
+    }
+
+    let volumes = movement::SpeedVolumes::gather(scene);
+    let rails = GrindRails::gather(scene);
+    let Some(bots) = backpack.get_mut::<Bots>() else {
+      return;
+    };
+
+    for (player_id, entity, position, next_checkpoint, following_checkpoint, others) in views {
+      let Ok((transform, player_movement, physics, meter, bump, wall, item)) = scene
+        .query_one_mut::<(
+          &TransformComponent,
+          &mut PlayerMovementComponent,
+          &mut PhysicsComponent,
+          Option<&BoostMeterComponent>,
+          Option<&BumpComponent>,
+          Option<&WallContactComponent>,
+          Option<&ItemComponent>,
+        )>(entity)
+      else {
+        continue;
+      };
+
+      let view = BotView {
+        position,
+        forward: transform.get_euler_direction().into_inner(),
+        speed_ratio: player_movement.current_velocity / player_movement.max_velocity.max(1.0),
+        boost: meter.map_or(0.0, |meter| meter.ratio()),
+        airborne: !player_movement.grounded,
+        balance: player_movement.grind.map(|grind| grind.balance),
+        item: item.map_or(Ability::None, |item| item.held),
+        next_checkpoint,
+        following_checkpoint,
+        others: &others,
+      };
+      let input = bots.think(&player_id, &view, delta_time);
+
+      movement::apply_speed_volumes(&volumes, player_movement, transform, delta_time);
+      movement::apply_boost_meter(meter, player_movement);
+      movement::apply_bump(bump, player_movement, delta_time);
+      movement::apply_wall_contact(wall, player_movement);
+      movement::grind(
+        &self.physics_controller,
+        &rails,
+        &input,
+        player_movement,
+        physics,
+        transform,
+        delta_time,
+      );
+      movement::drive(
+        &self.physics_controller,
+        &input,
+        player_movement,
+        physics,
+        transform,
+        delta_time,
+      );
+      movement::hover(
+        &self.physics_controller,
+        player_movement,
+        physics,
+        transform,
+        self.running_time,
+        delta_time,
+      );
+      movement::update_state(player_movement, &input, delta_time);
+    }
+  }
+}
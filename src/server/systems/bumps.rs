@@ -0,0 +1,163 @@
+use crate::server::players::Players;
+use crate::server::race::Race;
+use crate::shared::components::{BumpComponent, ItemComponent, RespawnComponent};
+
+use engine::application::components::PhysicsComponent;
+use engine::application::scene::{Scene, TransformComponent};
+use engine::networking::connection::PlayerId;
+use engine::systems::{physics::PhysicsController, Backpack, Initializable, Inventory, System};
+use engine::utils::units::Time;
+use nalgebra::Vector3;
+use rapier3d::prelude::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy)]
+struct Board {
+  player_id: PlayerId,
+  position: Vector3<f32>,
+  velocity: Vector3<f32>,
+  invulnerable: bool,
+}
+
+/// Bumps boards apart when they hit each other. Movement sets every board's
+/// velocity outright each tick, so the physics contact alone barely moves
+/// them; instead, every contact the physics reports between two racers'
+/// boards closing faster than `BumpComponent::min_speed` gives both a push
+/// away from each other and a moment without control, harder the faster
+/// they closed. The board that drove into the other gets a ram. Racers that
+/// were just respawned or have a shield up don't bump or get bumped.
+pub struct BumpSystem {
+  physics_controller: PhysicsController,
+  last_positions: HashMap<PlayerId, Vector3<f32>>,
+}
+
+impl Initializable for BumpSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let physics_controller = inventory.get::<PhysicsController>().clone();
+
+    Self {
+      physics_controller,
+      last_positions: HashMap::new(),
+    }
+  }
+}
+
+impl System for BumpSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    let Some(players) = backpack.get::<Players>() else {
+      return;
+    };
+
+    let racers = players.ids();
+    self
+      .last_positions
+      .retain(|player_id, _| racers.contains(player_id));
+
+    // which racer's board each collider belongs to, and how it's moving
+    let mut boards = HashMap::new();
+    for player_id in racers {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      let Ok((transform, physics, respawn, item)) = scene.query_one_mut::<(
+        &TransformComponent,
+        &PhysicsComponent,
+        Option<&RespawnComponent>,
+        Option<&ItemComponent>,
+      )>(player.player) else {
+        continue;
+      };
+
+      let position = transform.translation;
+      let velocity = self
+        .last_positions
+        .insert(player_id, position)
+        .map_or(Vector3::zeros(), |last| {
+          (position - last) / delta_time.max(f32::EPSILON)
+        });
+      if let Some(collider) = self.physics_controller.get_collider(&physics.joint.id) {
+        boards.insert(
+          collider,
+          Board {
+            player_id,
+            position,
+            velocity,
+            invulnerable: respawn.is_some_and(|respawn| respawn.is_invulnerable())
+              || item.is_some_and(|item| item.shield > 0.0),
+          },
+        );
+      }
+    }
+
+    let mut rams = vec![];
+    for event in self.physics_controller.collision_events() {
+      let CollisionEvent::Started(first, second, _) = event else {
+        continue;
+      };
+      let (Some(first), Some(second)) = (boards.get(&first), boards.get(&second)) else {
+        continue;
+      };
+      if first.invulnerable || second.invulnerable {
+        continue;
+      }
+
+      let towards = Vector3::new(
+        second.position.x - first.position.x,
+        0.0,
+        second.position.z - first.position.z,
+      )
+      .try_normalize(f32::EPSILON)
+      .unwrap_or_else(Vector3::x);
+      let first_closing = first.velocity.dot(&towards);
+      let second_closing = -second.velocity.dot(&towards);
+      let speed = first_closing + second_closing;
+
+      let (rammer, victim) = if first_closing >= second_closing {
+        (first, second)
+      } else {
+        (second, first)
+      };
+      let pushed = [(first, -towards), (second, towards)];
+
+      let mut bumped = false;
+      for (board, direction) in pushed {
+        let Some(player) = players.get(&board.player_id) else {
+          continue;
+        };
+        let Ok(bump) = scene.query_one_mut::<&mut BumpComponent>(player.player) else {
+          continue;
+        };
+        if speed < bump.min_speed {
+          continue;
+        }
+
+        let impulse = (speed * bump.impulse_per_speed).min(bump.max_impulse);
+        bump.bumps += 1;
+        bump.impulse = direction * impulse;
+        bump.stun = bump.stun_time * impulse / bump.max_impulse.max(f32::EPSILON);
+        bump.speed = speed;
+        if board.player_id == rammer.player_id {
+          bump.rams += 1;
+        }
+        bumped = true;
+      }
+
+      if bumped {
+        log::info!(
+          "{:?} rammed {:?} at {:.1}",
+          rammer.player_id,
+          victim.player_id,
+          speed
+        );
+        rams.push(rammer.player_id);
+      }
+    }
+
+    if let Some(race) = backpack.get_mut::<Race>() {
+      for player_id in rams {
+        race.count_ram(&player_id);
+      }
+    }
+  }
+}
@@ -0,0 +1,319 @@
+use crate::server::ghosts::{GhostEntities, GhostLap, GhostStore};
+use crate::server::level::Level;
+use crate::server::network_controller::{equip_character, send_entities};
+use crate::server::players::Players;
+use crate::server::race::{Race, RaceEvent};
+use crate::shared::components::{ExpiryComponent, GhostComponent, Role};
+use crate::shared::replay::{Replay, ReplayFrame, ReplayRacer, ReplayTransform};
+
+use engine::application::components::{AnimationComponent, ModelComponent, ParentComponent};
+use engine::application::{
+  input::TrustedInput,
+  scene::{Prefab, PrefabId, Scene, TransformComponent},
+};
+use engine::networking::connection::PlayerId;
+use engine::systems::{network::ClientSender, Backpack, Initializable, Inventory, System};
+use engine::Entity;
+use std::collections::HashMap;
+
+/// Seconds between two samples of a lap trace.
+const SAMPLE_INTERVAL: f32 = 1.0 / 20.0;
+/// Seconds a ghost stays at the finish line once its lap has played out.
+const GHOST_LINGER: f32 = 2.0;
+/// Seconds a ghost that got replaced has left, so its owner's client hears
+/// about it before the server drops it.
+const RETIRE_TIME: f32 = 0.2;
+
+struct Ghost {
+  entity: Entity,
+  hoverboard: Entity,
+  lap: GhostLap,
+}
+
+/// Traces every racer's laps into the `GhostStore`, and gives each player
+/// ghosts of their personal best and of the track's best lap, spawned afresh
+/// whenever they start a lap. Ghosts are only sent to the player racing them.
+pub struct GhostSystem {
+  client_sender: ClientSender<TrustedInput>,
+  traces: HashMap<PlayerId, Replay>,
+  lap_started: HashMap<PlayerId, f32>,
+  ghosts: HashMap<PlayerId, Vec<Ghost>>,
+}
+
+impl Initializable for GhostSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let client_sender = inventory.get::<ClientSender<TrustedInput>>().clone();
+
+    Self {
+      client_sender,
+      traces: HashMap::new(),
+      lap_started: HashMap::new(),
+      ghosts: HashMap::new(),
+    }
+  }
+}
+
+impl System for GhostSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let level_name = backpack.get::<Level>().unwrap().name.clone();
+    let Some(store) = backpack.get_mut::<GhostStore>() else {
+      return;
+    };
+    if store.level() != level_name {
+      store.load(&level_name);
+      self.clear(scene, backpack);
+    }
+
+    let (Some(players), Some(race)) = (backpack.get::<Players>(), backpack.get::<Race>()) else {
+      return;
+    };
+    let time = race.elapsed();
+    let events = race.events().to_vec();
+    let racers = players.ids();
+
+    let mut completed = vec![];
+    let mut started = vec![];
+    for event in events {
+      match event {
+        RaceEvent::LapCompleted {
+          player_id,
+          time: lap_time,
+          splits,
+          ..
+        } => {
+          if let Some(trace) = self.traces.remove(&player_id) {
+            completed.push((
+              player_id,
+              GhostLap {
+                time: lap_time,
+                splits,
+                trace,
+              },
+            ));
+          }
+        }
+        RaceEvent::LapStarted { player_id } => {
+          let Some(player) = players.get(&player_id) else {
+            continue;
+          };
+          let mut trace = Replay::new(&level_name);
+          trace.racers.push(ReplayRacer {
+            id: *player_id,
+            username: players.username(&player_id).to_string(),
+            loadout: player.loadout.clone(),
+          });
+          started.push((player_id, trace));
+        }
+        RaceEvent::Checkpoint { .. } | RaceEvent::Finished { .. } => {}
+      }
+    }
+
+    // ghosts of players that stopped racing go with them
+    let gone: Vec<PlayerId> = self
+      .ghosts
+      .keys()
+      .filter(|player_id| !racers.contains(player_id))
+      .copied()
+      .collect();
+    for player_id in gone {
+      self.retire_ghosts(scene, backpack, &player_id);
+      self.traces.remove(&player_id);
+      self.lap_started.remove(&player_id);
+    }
+
+    if let Some(store) = backpack.get_mut::<GhostStore>() {
+      for (player_id, lap) in completed {
+        store.submit(*player_id, lap);
+      }
+    }
+
+    // a lap that just set a record shows up as a ghost from the next lap on
+    for (player_id, trace) in started {
+      self.traces.insert(player_id, trace);
+      self.lap_started.insert(player_id, time);
+
+      self.retire_ghosts(scene, backpack, &player_id);
+      self.spawn_ghosts(scene, backpack, player_id);
+    }
+
+    self.record(scene, backpack, time);
+    self.move_ghosts(scene, time);
+  }
+}
+
+impl GhostSystem {
+  fn record(&mut self, scene: &mut Scene, backpack: &Backpack, time: f32) {
+    let Some(players) = backpack.get::<Players>() else {
+      return;
+    };
+
+    for (player_id, trace) in &mut self.traces {
+      let lap_time = time - self.lap_started.get(player_id).copied().unwrap_or(time);
+      let due = trace
+        .frames
+        .last()
+        .map_or(true, |frame| lap_time - frame.time >= SAMPLE_INTERVAL);
+      if !due {
+        continue;
+      }
+
+      let Some(player) = players.get(player_id) else {
+        continue;
+      };
+      if let Ok(transform) = scene.query_one_mut::<&TransformComponent>(player.player) {
+        trace.frames.push(ReplayFrame {
+          time: lap_time,
+          transforms: vec![ReplayTransform::new(
+            0,
+            &transform.translation,
+            &transform.rotation,
+          )],
+        });
+      }
+    }
+  }
+
+  fn move_ghosts(&mut self, scene: &mut Scene, time: f32) {
+    for (player_id, ghosts) in &self.ghosts {
+      let Some(lap_started) = self.lap_started.get(player_id) else {
+        continue;
+      };
+      let lap_time = time - lap_started;
+
+      for ghost in ghosts {
+        let Some((_, translation, rotation)) = ghost.lap.trace.sample(lap_time).pop() else {
+          continue;
+        };
+        if let Ok(transform) = scene.query_one_mut::<&mut TransformComponent>(ghost.entity) {
+          transform.translation = translation;
+          transform.rotation = rotation;
+        }
+      }
+    }
+  }
+
+  /// Spawns the player's personal best ghost, and the track's best unless
+  /// that is the player's own, and sends them to the player. Bots have
+  /// nobody to show ghosts to.
+  fn spawn_ghosts(&mut self, scene: &mut Scene, backpack: &mut Backpack, player_id: PlayerId) {
+    let (Some(store), Some(level), Some(players)) = (
+      backpack.get::<GhostStore>(),
+      backpack.get::<Level>(),
+      backpack.get::<Players>(),
+    ) else {
+      return;
+    };
+    if players.is_bot(&player_id) {
+      return;
+    }
+
+    let mut laps = vec![];
+    if let Some(lap) = store.personal(&player_id) {
+      laps.push((lap.clone(), false));
+    }
+    if let Some(lap) = store.track_best()
+      && lap.owner() != Some(*player_id)
+    {
+      laps.push((lap.clone(), true));
+    }
+
+    let ghosts: Vec<Ghost> = laps
+      .into_iter()
+      .filter_map(|(lap, track_best)| spawn_ghost(scene, level, lap, track_best))
+      .collect();
+    if ghosts.is_empty() {
+      return;
+    }
+
+    let entities: Vec<Entity> = ghosts
+      .iter()
+      .flat_map(|ghost| [ghost.entity, ghost.hoverboard])
+      .collect();
+    send_entities(&self.client_sender, scene, &entities, &player_id);
+
+    self.ghosts.insert(player_id, ghosts);
+    if let Some(spawned) = backpack.get_mut::<GhostEntities>() {
+      spawned.insert(player_id, entities);
+    }
+  }
+
+  /// Lets the player's current ghosts expire, on their client as well.
+  fn retire_ghosts(&mut self, scene: &mut Scene, backpack: &mut Backpack, player_id: &PlayerId) {
+    if let Some(spawned) = backpack.get_mut::<GhostEntities>() {
+      spawned.remove(player_id);
+    }
+    for ghost in self.ghosts.remove(player_id).into_iter().flatten() {
+      for entity in [ghost.entity, ghost.hoverboard] {
+        if let Ok(expiry) = scene.query_one_mut::<&mut ExpiryComponent>(entity) {
+          expiry.remaining = expiry.remaining.min(RETIRE_TIME);
+        }
+      }
+    }
+  }
+
+  fn clear(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let player_ids: Vec<PlayerId> = self.ghosts.keys().copied().collect();
+    for player_id in player_ids {
+      self.retire_ghosts(scene, backpack, &player_id);
+    }
+    self.traces.clear();
+    self.lap_started.clear();
+  }
+}
+
+/// Builds a ghost from the level's avatar prefab, dressed with the lap
+/// owner's character and board. Only what it takes to draw it is kept, so
+/// nothing added to racers ends up on ghosts, and clients swap in the
+/// translucent ghost look of each. Ghosts expire once their lap has played
+/// out.
+fn spawn_ghost(scene: &mut Scene, level: &Level, lap: GhostLap, track_best: bool) -> Option<Ghost> {
+  let loadout = lap.trace.racers.first()?.loadout.clone();
+  let lifetime = ExpiryComponent {
+    remaining: lap.time + GHOST_LINGER,
+  };
+
+  let mut prefab: Prefab = level.prefabs.first(Role::PlayerAvatar)?.clone();
+  let mut board: Prefab = level.board(loadout.board.as_deref())?.clone();
+  let board_look = level.ghost_look(&board);
+  let avatar_look = level
+    .character(loadout.avatar.as_deref())
+    .and_then(|character| level.ghost_look(character));
+  keep_drawable(&mut prefab);
+  keep_drawable(&mut board);
+  for (ghost, look) in [(&mut prefab, avatar_look), (&mut board, board_look)] {
+    ghost.components.push(Box::new(GhostComponent {
+      track_best,
+      look: look.unwrap_or_default(),
+    }));
+    ghost.components.push(Box::new(lifetime));
+  }
+
+  let prefab_id = PrefabId::new();
+  *prefab.id = prefab_id.clone();
+  *board.id = PrefabId::new();
+
+  let entity = scene.create_raw_entity("Ghost");
+  scene.create_with_prefab(entity, prefab);
+  equip_character(scene, level, entity, loadout.avatar.as_deref());
+
+  let hoverboard = scene.create_raw_entity("Ghost Hoverboard");
+  scene.create_with_prefab(hoverboard, board);
+  if let Ok(parent) = scene.query_one_mut::<&mut ParentComponent>(hoverboard) {
+    parent.id = prefab_id;
+  }
+
+  Some(Ghost {
+    entity,
+    hoverboard,
+    lap,
+  })
+}
+
+fn keep_drawable(prefab: &mut Prefab) {
+  prefab.components.retain(|component| {
+    let component = component.as_any();
+    component.is::<ModelComponent>()
+      || component.is::<AnimationComponent>()
+      || component.is::<ParentComponent>()
+  });
+}
@@ -0,0 +1,236 @@
+use crate::server::bots::{racer_input, Bots};
+use crate::server::level::Level;
+use crate::server::network_controller::send_entities;
+use crate::server::players::Players;
+use crate::shared::components::{
+  Ability, BoostMeterComponent, ExpiryComponent, ItemBoxComponent, ItemComponent,
+  ItemPoolComponent, RacerComponent, Role,
+};
+use crate::shared::input::{Actions, PlayerInput};
+use crate::shared::volume::BoxVolume;
+
+use engine::application::{
+  input::TrustedInput,
+  scene::{PrefabId, Scene, TransformComponent},
+};
+use engine::networking::connection::PlayerId;
+use engine::systems::{
+  input::InputsReader, network::ClientSender, Backpack, Initializable, Inventory, System,
+};
+use engine::utils::units::Time;
+use engine::Entity;
+use nalgebra::Vector3;
+use std::collections::{HashMap, HashSet};
+
+/// Seconds a smoke cloud hangs around.
+const SMOKE_TIME: f32 = 10.0;
+/// How far behind the board a smoke cloud is dropped.
+const SMOKE_DISTANCE: f32 = 2.0;
+/// Seconds a shield keeps the racer from being bumped.
+const SHIELD_TIME: f32 = 4.0;
+
+/// Hands out items from the level's item boxes and uses them. Driving
+/// through an available box fills some of the racer's boost meter and, if
+/// their hands are empty, gives them an item rolled from the level's
+/// `ItemPoolComponent` for their place. The box then disappears for a while.
+/// Pressing `Actions::UseItem` uses whatever the racer holds. Smoke clouds
+/// are sent to everyone as they're dropped and expire on their own.
+pub struct ItemSystem {
+  client_sender: ClientSender<TrustedInput>,
+  inputs: InputsReader<PlayerInput>,
+  /// Racers holding `Actions::UseItem`, so holding it only uses one item.
+  using: HashSet<PlayerId>,
+  /// Taken boxes and the seconds until they're back.
+  taken: HashMap<Entity, f32>,
+}
+
+impl Initializable for ItemSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let client_sender = inventory.get::<ClientSender<TrustedInput>>().clone();
+    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
+
+    Self {
+      client_sender,
+      inputs,
+      using: HashSet::new(),
+      taken: HashMap::new(),
+    }
+  }
+}
+
+impl System for ItemSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    self.restock(scene, delta_time);
+
+    let (Some(players), Some(level)) = (backpack.get::<Players>(), backpack.get::<Level>()) else {
+      return;
+    };
+    let bots = backpack.get::<Bots>();
+    let pool = scene
+      .query_mut::<&ItemPoolComponent>()
+      .into_iter()
+      .next()
+      .map(|(_, pool)| pool.clone())
+      .unwrap_or_default();
+    let boxes: Vec<(Entity, BoxVolume, f32)> = scene
+      .query_mut::<(&ItemBoxComponent, &TransformComponent)>()
+      .into_iter()
+      .filter(|(_, (item_box, _))| item_box.available)
+      .map(|(entity, (item_box, transform))| {
+        (
+          entity,
+          BoxVolume::new(transform, item_box.half_extents),
+          item_box.respawn_time,
+        )
+      })
+      .collect();
+
+    let racers = players.ids();
+    let racer_count = racers.len();
+    self.using.retain(|player_id| racers.contains(player_id));
+
+    let mut smoke_at = vec![];
+    for player_id in racers {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      let using = racer_input(&self.inputs, bots, &player_id)
+        .is_some_and(|input| input.actions.contains(&Actions::UseItem));
+      let pressed = using && self.using.insert(player_id);
+      if !using {
+        self.using.remove(&player_id);
+      }
+
+      let Ok((transform, item, racer, mut meter)) = scene.query_one_mut::<(
+        &TransformComponent,
+        &mut ItemComponent,
+        Option<&RacerComponent>,
+        Option<&mut BoostMeterComponent>,
+      )>(player.player) else {
+        continue;
+      };
+      let position = transform.translation;
+      if item.shield > 0.0 {
+        item.shield = (item.shield - delta_time).max(0.0);
+      }
+
+      let picked = boxes
+        .iter()
+        .find(|(entity, volume, _)| !self.taken.contains_key(entity) && volume.contains(&position));
+      if let Some((entity, _, respawn_time)) = picked {
+        self.taken.insert(*entity, *respawn_time);
+        if let Some(meter) = meter.as_deref_mut() {
+          meter.fill(meter.pickup_fill);
+        }
+        if item.held == Ability::None {
+          let place = racer.map_or(1, |racer| racer.place);
+          item.held = roll(&pool, place, racer_count);
+          log::info!("{:?} picked up {:?}", player_id, item.held);
+        }
+      }
+
+      if !pressed || item.held == Ability::None {
+        continue;
+      }
+      let ability = std::mem::take(&mut item.held);
+      item.uses += 1;
+      item.used = ability;
+      log::info!("{:?} used {:?}", player_id, ability);
+
+      match ability {
+        Ability::SmokeBomb => {
+          let forward = transform.get_euler_direction().into_inner();
+          smoke_at.push(position - forward * SMOKE_DISTANCE);
+        }
+        Ability::Boost => {
+          if let Some(meter) = meter {
+            meter.fill(meter.capacity);
+          }
+        }
+        Ability::Shield => {
+          item.shield = item.shield.max(SHIELD_TIME);
+        }
+        Ability::None => {}
+      }
+    }
+
+    for entity in self.taken.keys() {
+      if let Ok(item_box) = scene.query_one_mut::<&mut ItemBoxComponent>(*entity)
+        && item_box.available
+      {
+        item_box.available = false;
+      }
+    }
+
+    let clouds: Vec<Entity> = smoke_at
+      .into_iter()
+      .filter_map(|position| spawn_smoke(scene, level, position))
+      .collect();
+    if clouds.is_empty() {
+      return;
+    }
+    for player_id in players.connected_ids() {
+      send_entities(&self.client_sender, scene, &clouds, &player_id);
+    }
+  }
+}
+
+impl ItemSystem {
+  /// Brings taken boxes back once their time is up.
+  fn restock(&mut self, scene: &mut Scene, delta_time: f32) {
+    let mut restocked = vec![];
+    self.taken.retain(|entity, left| {
+      *left -= delta_time;
+      if *left > 0.0 {
+        return true;
+      }
+      restocked.push(*entity);
+      false
+    });
+
+    for entity in restocked {
+      if let Ok(item_box) = scene.query_one_mut::<&mut ItemBoxComponent>(entity) {
+        item_box.available = true;
+      }
+    }
+  }
+}
+
+/// Picks an item at random from `pool`, weighted for the racer in `place`.
+fn roll(pool: &ItemPoolComponent, place: u32, racers: usize) -> Ability {
+  let weights = pool.weights(place, racers);
+  let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+  if total <= 0.0 {
+    return Ability::None;
+  }
+
+  let mut pick = rand::random::<f32>() * total;
+  for (ability, weight) in &weights {
+    if pick < *weight {
+      return *ability;
+    }
+    pick -= weight;
+  }
+  weights
+    .iter()
+    .rev()
+    .find(|(_, weight)| *weight > 0.0)
+    .map_or(Ability::None, |(ability, _)| *ability)
+}
+
+fn spawn_smoke(scene: &mut Scene, level: &Level, position: Vector3<f32>) -> Option<Entity> {
+  let mut prefab = level
+    .prefabs
+    .find(Role::AbilityEffect, "Smoke Bomb")?
+    .clone();
+  prefab.transform.translation = position;
+  prefab.components.push(Box::new(ExpiryComponent {
+    remaining: SMOKE_TIME,
+  }));
+  *prefab.id = PrefabId::new();
+
+  let entity = scene.create_raw_entity("Smoke Bomb");
+  scene.create_with_prefab(entity, prefab);
+  Some(entity)
+}
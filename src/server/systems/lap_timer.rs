@@ -0,0 +1,80 @@
+use crate::server::ghosts::GhostStore;
+use crate::server::players::Players;
+use crate::server::race::{Race, RaceEvent};
+use crate::shared::components::LapTimerComponent;
+
+use engine::application::scene::Scene;
+use engine::systems::{Backpack, Initializable, Inventory, System};
+
+/// Copies lap and split times onto each racer's `LapTimerComponent`, with
+/// splits compared against the racer's personal best lap. Runs before the
+/// `GhostSystem` so a record lap is compared against the one it beat.
+pub struct LapTimerSystem {}
+
+impl Initializable for LapTimerSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl System for LapTimerSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let (Some(players), Some(race)) = (backpack.get::<Players>(), backpack.get::<Race>()) else {
+      return;
+    };
+    let store = backpack.get::<GhostStore>();
+
+    for event in race.events() {
+      let player_id = match event {
+        RaceEvent::Checkpoint { player_id, .. }
+        | RaceEvent::LapCompleted { player_id, .. }
+        | RaceEvent::LapStarted { player_id } => player_id,
+        RaceEvent::Finished { .. } => continue,
+      };
+      let Some(player) = players.get(player_id) else {
+        continue;
+      };
+      let Ok(timer) = scene.query_one_mut::<&mut LapTimerComponent>(player.player) else {
+        continue;
+      };
+      let personal_best = store.and_then(|store| store.personal(player_id));
+
+      match event {
+        RaceEvent::Checkpoint {
+          checkpoint,
+          lap,
+          split,
+          ..
+        } => {
+          let index = race
+            .progress(player_id)
+            .map_or(0, |progress| progress.splits.len().saturating_sub(1));
+          let best_split = personal_best.and_then(|best| best.splits.get(index));
+
+          timer.lap = *lap;
+          timer.checkpoint = *checkpoint;
+          timer.split = *split;
+          timer.has_split_delta = best_split.is_some();
+          timer.split_delta = best_split.map_or(0.0, |best| split - best);
+        }
+        RaceEvent::LapCompleted { lap, time, .. } => {
+          let best_lap = personal_best.map_or(*time, |best| best.time.min(*time));
+
+          timer.lap = *lap;
+          timer.checkpoint = 0;
+          timer.split = *time;
+          timer.has_split_delta = personal_best.is_some();
+          timer.split_delta = personal_best.map_or(0.0, |best| time - best.time);
+          timer.last_lap = *time;
+          timer.best_lap = best_lap;
+        }
+        RaceEvent::LapStarted { .. } => {
+          if let Some(best) = personal_best {
+            timer.best_lap = best.time;
+          }
+        }
+        RaceEvent::Finished { .. } => {}
+      }
+    }
+  }
+}
@@ -0,0 +1,157 @@
+use crate::server::bots::Bots;
+use crate::server::ghosts::GhostEntities;
+use crate::server::level::{Level, LevelRotation};
+use crate::server::network_controller::{join_race, spawn_player, sync_world};
+use crate::server::players::Players;
+use crate::server::race::{GameMode, Race};
+use crate::server::replay::ReplayRecorder;
+
+use engine::application::downloader::DownloadSender;
+use engine::application::{input::TrustedInput, scene::Scene};
+use engine::systems::{network::ClientSender, Backpack, Initializable, Inventory, System};
+use engine::utils::units::Time;
+
+/// How long everyone gets to look at the results before the next level.
+const RESULTS_TIME: f32 = 10.0;
+
+/// Swaps the current level for the next one in the rotation once a race is
+/// over, without restarting the server. A race is over `RESULTS_TIME` seconds
+/// after every racer has finished, or once it has run for `ROUND_DURATION`
+/// seconds when that env var is set.
+pub struct LevelRotationSystem {
+  download_sender: DownloadSender,
+  client_sender: ClientSender<TrustedInput>,
+  round_duration: Option<f32>,
+  /// Seconds since the last racer finished.
+  results_time: f32,
+}
+
+impl Initializable for LevelRotationSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let download_sender = inventory.get::<DownloadSender>().clone();
+    let client_sender = inventory.get::<ClientSender<TrustedInput>>().clone();
+    let round_duration = dotenv::var("ROUND_DURATION")
+      .ok()
+      .and_then(|duration| duration.parse::<f32>().ok());
+
+    Self {
+      download_sender,
+      client_sender,
+      round_duration,
+      results_time: 0.0,
+    }
+  }
+}
+
+impl System for LevelRotationSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    let (Some(race), Some(players)) = (backpack.get::<Race>(), backpack.get::<Players>()) else {
+      return;
+    };
+    // a time trial lasts as long as the player keeps at it
+    if race.mode() == GameMode::TimeTrial {
+      return;
+    }
+
+    self.results_time = if race.is_over(&players.ids()) {
+      self.results_time + delta_time
+    } else {
+      0.0
+    };
+    let out_of_time = self
+      .round_duration
+      .is_some_and(|round_duration| race.elapsed() >= round_duration);
+
+    if self.results_time >= RESULTS_TIME || out_of_time {
+      self.results_time = 0.0;
+      self.next_level(scene, backpack);
+    }
+  }
+}
+
+impl LevelRotationSystem {
+  pub fn next_level(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let Some(level) = backpack.get_mut::<Level>() else {
+      return;
+    };
+    level.unload(scene);
+
+    // ghosts raced on the old level, and would be sent along with the new one
+    if let Some(ghosts) = backpack.get_mut::<GhostEntities>() {
+      for entity in ghosts.drain() {
+        let _ = scene.despawn(entity);
+      }
+    }
+
+    let Some(rotation) = backpack.get_mut::<LevelRotation>() else {
+      return;
+    };
+    rotation.advance();
+    let next = Level::load_playable(&self.download_sender, scene, rotation);
+    let name = next.name.clone();
+    *backpack.get_mut::<Level>().unwrap() = next;
+
+    if let Some(recorder) = backpack.get_mut::<ReplayRecorder>() {
+      recorder.finish(&name);
+    }
+
+    let player_ids = match backpack.get::<Players>() {
+      Some(players) => players.ids(),
+      None => return,
+    };
+
+    for (slot, player_id) in player_ids.into_iter().enumerate() {
+      let Some(old_entities) = backpack.get::<Players>().unwrap().get(&player_id).cloned() else {
+        continue;
+      };
+      let _ = scene.despawn(old_entities.player);
+      let _ = scene.despawn(old_entities.hoverboard);
+
+      let entity = scene.create_raw_entity("Player");
+      let level = backpack.get::<Level>().unwrap();
+      let player_entities =
+        spawn_player(scene, level, entity, player_id, slot, old_entities.loadout);
+
+      let players = backpack.get_mut::<Players>().unwrap();
+      match player_entities {
+        Some(player_entities) => players.insert(player_id, player_entities),
+        // nothing to race as on this level, so players watch it instead and
+        // bots leave
+        None if players.is_bot(&player_id) => {
+          let _ = scene.despawn(entity);
+          players.remove_bot(&player_id);
+          if let Some(bots) = backpack.get_mut::<Bots>() {
+            bots.remove(&player_id);
+          }
+        }
+        None => {
+          let _ = scene.despawn(entity);
+          players.remove(&player_id);
+          players.insert_spectator(player_id);
+        }
+      }
+    }
+
+    // spectators get to race again, unless they asked to keep watching
+    for player_id in backpack.get::<Players>().unwrap().spectator_ids() {
+      if !backpack
+        .get::<Players>()
+        .unwrap()
+        .loadout(&player_id)
+        .spectate
+      {
+        let entity = scene.create_raw_entity("Player");
+        join_race(scene, backpack, entity, player_id);
+      }
+    }
+
+    if let Some(race) = backpack.get_mut::<Race>() {
+      race.restart();
+    }
+
+    for player_id in backpack.get::<Players>().unwrap().connected_ids() {
+      sync_world(&self.client_sender, scene, backpack, &player_id);
+    }
+  }
+}
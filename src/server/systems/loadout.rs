@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use crate::server::network_controller::{admit_player, join_race, spectate, sync_world};
+use crate::server::players::Players;
+use crate::server::race::Race;
+use crate::shared::input::PlayerInput;
+
+use engine::application::{input::TrustedInput, scene::Scene};
+use engine::networking::connection::PlayerId;
+use engine::systems::{
+  input::InputsReader, network::ClientSender, Backpack, Initializable, Inventory, System,
+};
+use engine::utils::units::Time;
+
+/// How long to wait for a join message before giving up on it and seating the
+/// player with the loadout they last picked.
+const JOIN_TIMEOUT: f32 = 3.0;
+
+/// How long finished racers keep their board, to see their final lap time,
+/// before they're moved over to spectating.
+const FINISHED_TIME: f32 = 3.0;
+
+/// Seats players once the join message with their loadout arrives. Racers that
+/// toggle spectating or finished the race leave it, and spectators that didn't
+/// ask to watch take a free spot once the race takes racers again.
+pub struct LoadoutSystem {
+  inputs: InputsReader<PlayerInput>,
+  client_sender: ClientSender<TrustedInput>,
+  waiting: HashMap<PlayerId, f32>,
+  /// Seconds since each racer still on the track finished.
+  finished: HashMap<PlayerId, f32>,
+}
+
+impl Initializable for LoadoutSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
+    let client_sender = inventory.get::<ClientSender<TrustedInput>>().clone();
+
+    Self {
+      inputs,
+      client_sender,
+      waiting: HashMap::new(),
+      finished: HashMap::new(),
+    }
+  }
+}
+
+impl System for LoadoutSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    let Some(players) = backpack.get::<Players>() else {
+      return;
+    };
+
+    let pending = players.pending();
+    self
+      .waiting
+      .retain(|player_id, _| pending.iter().any(|(id, _)| id == player_id));
+
+    let mut joins = vec![];
+    for (player_id, entity) in pending {
+      let waited = self.waiting.entry(player_id).or_default();
+      *waited += delta_time;
+
+      if let Some(loadout) = self
+        .inputs
+        .read_for(&player_id)
+        .and_then(|input| input.join)
+      {
+        joins.push((player_id, entity, loadout));
+      } else if *waited >= JOIN_TIMEOUT {
+        log::warn!("{player_id:?} never sent a join message, using their last loadout");
+        joins.push((player_id, entity, players.loadout(&player_id)));
+      }
+    }
+
+    for (player_id, entity, loadout) in joins {
+      log::info!("{:?} joined with {:?}", player_id, loadout);
+
+      self.waiting.remove(&player_id);
+      if let Some(players) = backpack.get_mut::<Players>() {
+        players.remove_pending(&player_id);
+      }
+      admit_player(
+        &self.client_sender,
+        scene,
+        backpack,
+        entity,
+        player_id,
+        loadout,
+      );
+    }
+
+    self.switch_roles(scene, backpack);
+    self.retire_finishers(scene, backpack, delta_time);
+    self.seat_spectators(scene, backpack);
+  }
+}
+
+impl LoadoutSystem {
+  fn retire_finishers(&mut self, scene: &mut Scene, backpack: &mut Backpack, delta_time: f32) {
+    let (Some(players), Some(race)) = (backpack.get::<Players>(), backpack.get::<Race>()) else {
+      return;
+    };
+
+    // bots stay on the track, they have nobody to watch the race for
+    let finished: Vec<_> = players
+      .ids()
+      .into_iter()
+      .filter(|player_id| !players.is_bot(player_id) && race.has_finished(player_id))
+      .collect();
+    self
+      .finished
+      .retain(|player_id, _| finished.contains(player_id));
+
+    for player_id in finished {
+      let waited = self.finished.entry(player_id).or_default();
+      *waited += delta_time;
+      if *waited < FINISHED_TIME {
+        continue;
+      }
+
+      log::info!(
+        "{:?} finished and is spectating the rest of the race",
+        player_id
+      );
+      self.finished.remove(&player_id);
+      spectate(scene, backpack, player_id);
+    }
+  }
+
+  fn switch_roles(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let Some(players) = backpack.get::<Players>() else {
+      return;
+    };
+
+    let mut toggled = vec![];
+    for player_id in players.connected_ids() {
+      let Some(input) = self.inputs.read_for(&player_id) else {
+        continue;
+      };
+      if players.loadout(&player_id).spectate != input.spectate {
+        toggled.push((player_id, input.spectate));
+      }
+    }
+
+    for (player_id, spectating) in toggled {
+      let Some(players) = backpack.get_mut::<Players>() else {
+        return;
+      };
+      let mut loadout = players.loadout(&player_id);
+      loadout.spectate = spectating;
+      players.set_loadout(player_id, loadout);
+
+      // spectators that stopped are seated by `seat_spectators`
+      if spectating && players.get(&player_id).is_some() {
+        log::info!("{:?} left the race to spectate", player_id);
+        spectate(scene, backpack, player_id);
+      }
+    }
+  }
+
+  fn seat_spectators(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let Some(players) = backpack.get::<Players>() else {
+      return;
+    };
+    let race = backpack.get::<Race>();
+    let mut racers = players.len();
+
+    let mut to_race = vec![];
+    for player_id in players.spectator_ids() {
+      if players.loadout(&player_id).spectate
+        || race.is_some_and(|race| race.has_finished(&player_id))
+      {
+        continue;
+      }
+      if race.is_some_and(|race| race.accepts_racers(racers)) {
+        racers += 1;
+        to_race.push(player_id);
+      }
+    }
+
+    for player_id in to_race {
+      log::info!("{:?} stopped spectating and joined the race", player_id);
+
+      let entity = scene.create_raw_entity("Player");
+      join_race(scene, backpack, entity, player_id);
+
+      sync_world(&self.client_sender, scene, backpack, &player_id);
+    }
+  }
+}
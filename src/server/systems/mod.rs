@@ -0,0 +1,15 @@
+pub mod boost_meter;
+pub mod bots;
+pub mod bumps;
+pub mod ghosts;
+pub mod items;
+pub mod lap_timer;
+pub mod level_rotation;
+pub mod loadout;
+pub mod race;
+pub mod ranking;
+pub mod recovery;
+pub mod replay;
+pub mod time_trial;
+pub mod tricks;
+pub mod walls;
@@ -0,0 +1,48 @@
+use crate::server::level::Level;
+use crate::server::players::Players;
+use crate::server::race::Race;
+
+use engine::application::scene::{Scene, TransformComponent};
+use engine::systems::{Backpack, Initializable, Inventory, System};
+use engine::utils::units::Time;
+
+/// Runs the race clock and moves racers through the level's checkpoints.
+/// Systems that care about laps run after this one and read
+/// `Race::events`.
+pub struct RaceSystem {}
+
+impl Initializable for RaceSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl System for RaceSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+
+    let mut positions = vec![];
+    if let Some(players) = backpack.get::<Players>() {
+      for player_id in players.ids() {
+        let Some(player) = players.get(&player_id) else {
+          continue;
+        };
+        if let Ok(transform) = scene.query_one_mut::<&TransformComponent>(player.player) {
+          positions.push((player_id, transform.translation));
+        }
+      }
+    }
+
+    let checkpoints = backpack.get::<Level>().unwrap().checkpoints.clone();
+    let Some(race) = backpack.get_mut::<Race>() else {
+      return;
+    };
+
+    race.tick(delta_time);
+    let racers: Vec<_> = positions.iter().map(|(player_id, _)| *player_id).collect();
+    race.retain_racers(&racers);
+    for (player_id, position) in positions {
+      race.update_racer(player_id, &position, &checkpoints);
+    }
+  }
+}
@@ -0,0 +1,290 @@
+use crate::server::level::Level;
+use crate::server::players::Players;
+use crate::server::race::{Progress, Race};
+use crate::shared::components::RacerComponent;
+
+use engine::application::scene::{Scene, TransformComponent};
+use engine::networking::connection::PlayerId;
+use engine::systems::{Backpack, Initializable, Inventory, System};
+use nalgebra::Vector3;
+use std::cmp::Ordering;
+
+/// Racers closer than this to each other, on the same lap and leg, share a
+/// place rather than flickering between two.
+const TIE_DISTANCE: f32 = 0.05;
+
+/// How far a racer has made it, for ranking.
+#[derive(Debug, Clone, Copy)]
+enum Standing {
+  Finished {
+    place: u32,
+  },
+  Racing {
+    lap: u32,
+    /// Checkpoints passed on the current lap.
+    leg: usize,
+    /// Left to go to the next checkpoint.
+    remaining: f32,
+  },
+  /// Not seen by the race yet, or racing a level without checkpoints.
+  Unranked,
+}
+
+impl Standing {
+  /// `Less` when `self` is ahead of `other`.
+  fn compare(&self, other: &Self) -> Ordering {
+    match (self, other) {
+      (Self::Finished { place }, Self::Finished { place: other }) => place.cmp(other),
+      (Self::Finished { .. }, _) => Ordering::Less,
+      (_, Self::Finished { .. }) => Ordering::Greater,
+      (
+        Self::Racing {
+          lap,
+          leg,
+          remaining,
+        },
+        Self::Racing {
+          lap: other_lap,
+          leg: other_leg,
+          remaining: other_remaining,
+        },
+      ) => other_lap
+        .cmp(lap)
+        .then(other_leg.cmp(leg))
+        .then(remaining.total_cmp(other_remaining)),
+      (Self::Racing { .. }, Self::Unranked) => Ordering::Less,
+      (Self::Unranked, Self::Racing { .. }) => Ordering::Greater,
+      (Self::Unranked, Self::Unranked) => Ordering::Equal,
+    }
+  }
+
+  fn tied(&self, other: &Self) -> bool {
+    match (self, other) {
+      (
+        Self::Racing {
+          lap,
+          leg,
+          remaining,
+        },
+        Self::Racing {
+          lap: other_lap,
+          leg: other_leg,
+          remaining: other_remaining,
+        },
+      ) => {
+        lap == other_lap && leg == other_leg && (remaining - other_remaining).abs() < TIE_DISTANCE
+      }
+      (Self::Unranked, Self::Unranked) => true,
+      _ => false,
+    }
+  }
+}
+
+/// Works out every racer's live place from laps done, checkpoints passed and
+/// how far they are from the next one, measured along the track spline when
+/// the level has one. Places go to `RacerComponent::place`, which is only
+/// touched when it changes so unchanged racers aren't sent to clients again.
+///
+/// Finished racers keep the place they finished in, and everyone still racing
+/// is placed behind all of them, counting finishers that left since. Tied
+/// racers share a place and the next one skips it, so two racers tied for 2nd
+/// are followed by the 4th. Racers that left mid-race are simply not ranked,
+/// and everyone behind them moves up.
+pub struct RankingSystem {}
+
+impl Initializable for RankingSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl System for RankingSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let (Some(players), Some(race), Some(level)) = (
+      backpack.get::<Players>(),
+      backpack.get::<Race>(),
+      backpack.get::<Level>(),
+    ) else {
+      return;
+    };
+
+    let mut standings: Vec<(PlayerId, Standing)> = vec![];
+    for player_id in players.ids() {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      let Ok(transform) = scene.query_one_mut::<&TransformComponent>(player.player) else {
+        continue;
+      };
+      let position = transform.translation;
+
+      let standing = match race.progress(&player_id) {
+        Some(Progress {
+          finished: Some(place),
+          ..
+        }) => Standing::Finished { place: *place },
+        Some(progress) if !level.checkpoints.is_empty() => {
+          let count = level.checkpoints.len();
+          let next = progress.next_checkpoint % count;
+          Standing::Racing {
+            lap: progress.lap,
+            leg: (next + count - 1) % count,
+            remaining: remaining(level, next, &position),
+          }
+        }
+        _ => Standing::Unranked,
+      };
+      standings.push((player_id, standing));
+    }
+
+    standings.sort_by(|(_, a), (_, b)| a.compare(b));
+    let places = places(
+      &standings
+        .iter()
+        .map(|(_, standing)| *standing)
+        .collect::<Vec<_>>(),
+      race.finishers(),
+    );
+
+    for ((player_id, standing), place) in standings.iter().zip(places) {
+      let finished = matches!(standing, Standing::Finished { .. });
+
+      let Some(player) = players.get(player_id) else {
+        continue;
+      };
+      if let Ok(racer) = scene.query_one_mut::<&mut RacerComponent>(player.player)
+        && (racer.place != place || racer.finished != finished)
+      {
+        racer.place = place;
+        racer.finished = finished;
+      }
+    }
+  }
+}
+
+/// Places for `standings`, sorted leader first, given how many racers have
+/// finished in all.
+fn places(standings: &[Standing], finishers: u32) -> Vec<u32> {
+  let finished_here = standings
+    .iter()
+    .filter(|standing| matches!(standing, Standing::Finished { .. }))
+    .count();
+
+  let mut places: Vec<u32> = vec![];
+  for (index, standing) in standings.iter().enumerate() {
+    let place = match standing {
+      Standing::Finished { place } => *place,
+      _ if index > finished_here && standings[index - 1].tied(standing) => places[index - 1],
+      _ => finishers + (index - finished_here) as u32 + 1,
+    };
+    places.push(place);
+  }
+  places
+}
+
+/// Distance left to the racer's next checkpoint, along the track when the
+/// level has one and as the crow flies otherwise.
+fn remaining(level: &Level, next: usize, position: &Vector3<f32>) -> f32 {
+  let checkpoint = &level.checkpoints[next];
+  match (&level.track, checkpoint.distance) {
+    (Some(track), Some(distance)) => track.delta(track.distance_along(position), distance),
+    _ => (checkpoint.position - position).norm(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn racing(lap: u32, leg: usize, remaining: f32) -> Standing {
+    Standing::Racing {
+      lap,
+      leg,
+      remaining,
+    }
+  }
+
+  #[test]
+  fn finished_racers_keep_their_finishing_order() {
+    let first = Standing::Finished { place: 1 };
+    let second = Standing::Finished { place: 2 };
+    assert_eq!(first.compare(&second), Ordering::Less);
+    assert_eq!(second.compare(&first), Ordering::Greater);
+    assert_eq!(second.compare(&racing(9, 9, 0.0)), Ordering::Less);
+    assert_eq!(Standing::Unranked.compare(&first), Ordering::Greater);
+  }
+
+  #[test]
+  fn racers_rank_by_lap_then_leg_then_distance_left() {
+    assert_eq!(
+      racing(2, 0, 90.0).compare(&racing(1, 5, 1.0)),
+      Ordering::Less
+    );
+    assert_eq!(
+      racing(1, 3, 90.0).compare(&racing(1, 2, 1.0)),
+      Ordering::Less
+    );
+    assert_eq!(
+      racing(1, 2, 1.0).compare(&racing(1, 2, 5.0)),
+      Ordering::Less
+    );
+    assert_eq!(
+      racing(1, 2, 5.0).compare(&racing(1, 2, 1.0)),
+      Ordering::Greater
+    );
+    assert_eq!(
+      racing(0, 0, 0.0).compare(&Standing::Unranked),
+      Ordering::Less
+    );
+  }
+
+  #[test]
+  fn sorting_puts_the_leader_first() {
+    let mut standings = [
+      Standing::Unranked,
+      racing(1, 0, 10.0),
+      Standing::Finished { place: 2 },
+      racing(2, 1, 4.0),
+      Standing::Finished { place: 1 },
+    ];
+    standings.sort_by(|a, b| a.compare(b));
+    assert!(matches!(standings[0], Standing::Finished { place: 1 }));
+    assert!(matches!(standings[1], Standing::Finished { place: 2 }));
+    assert!(matches!(standings[2], Standing::Racing { lap: 2, .. }));
+    assert!(matches!(standings[3], Standing::Racing { lap: 1, .. }));
+    assert!(matches!(standings[4], Standing::Unranked));
+  }
+
+  #[test]
+  fn finishers_keep_their_place_after_others_leave() {
+    // 1st left, so only the 2nd finisher and two racers are still here
+    let standings = [
+      Standing::Finished { place: 2 },
+      racing(2, 1, 4.0),
+      racing(1, 0, 10.0),
+    ];
+    assert_eq!(places(&standings, 2), vec![2, 3, 4]);
+  }
+
+  #[test]
+  fn tied_racers_share_a_place() {
+    let standings = [
+      Standing::Finished { place: 1 },
+      racing(1, 2, 5.0),
+      racing(1, 2, 5.0 + TIE_DISTANCE / 2.0),
+      racing(1, 0, 5.0),
+      Standing::Unranked,
+    ];
+    assert_eq!(places(&standings, 1), vec![1, 2, 2, 4, 5]);
+  }
+
+  #[test]
+  fn only_racers_side_by_side_are_tied() {
+    assert!(racing(1, 2, 5.0).tied(&racing(1, 2, 5.0 + TIE_DISTANCE / 2.0)));
+    assert!(!racing(1, 2, 5.0).tied(&racing(1, 2, 5.0 + TIE_DISTANCE * 2.0)));
+    assert!(!racing(1, 2, 5.0).tied(&racing(1, 3, 5.0)));
+    assert!(!racing(1, 2, 5.0).tied(&racing(2, 2, 5.0)));
+    assert!(Standing::Unranked.tied(&Standing::Unranked));
+    assert!(!Standing::Finished { place: 1 }.tied(&Standing::Finished { place: 1 }));
+  }
+}
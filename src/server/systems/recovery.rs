@@ -0,0 +1,275 @@
+use crate::server::bots::{racer_input, Bots};
+use crate::server::level::Level;
+use crate::server::players::Players;
+use crate::server::race::Race;
+use crate::shared::components::{
+  PlayerMovementComponent, RecoveryComponent, RespawnComponent, RespawnReason,
+};
+use crate::shared::input::{Actions, PlayerInput};
+
+use engine::application::components::PhysicsComponent;
+use engine::application::scene::{Scene, TransformComponent};
+use engine::networking::connection::PlayerId;
+use engine::systems::{
+  input::InputsReader, physics::PhysicsController, Backpack, Initializable, Inventory, System,
+};
+use engine::utils::units::Time;
+use engine::Entity;
+use nalgebra::Vector3;
+use std::collections::HashMap;
+
+/// How far above the checkpoint racers are put back, so they drop onto the
+/// track instead of into it.
+const RESPAWN_HEIGHT: f32 = 1.0;
+/// Below this many units per second a racer isn't really driving, whichever
+/// way they face.
+const MIN_MOVING_SPEED: f32 = 1.0;
+/// Facing further than this from the track direction, out of 1, is driving
+/// the wrong way.
+const WRONG_WAY_DOT: f32 = -0.5;
+
+#[derive(Debug, Default)]
+struct Watch {
+  last_position: Option<Vector3<f32>>,
+  wrong_way: f32,
+  stuck: f32,
+  reset_held: bool,
+  /// Seconds until the reset the player asked for.
+  reset_pending: Option<f32>,
+  reset_cooldown: f32,
+}
+
+/// Puts racers back at their last checkpoint when they fall below the
+/// level's kill plane, leave its track bounds, drive the wrong way or get
+/// stuck, as set up by the level's `RecoveryComponent`. Players can also ask
+/// for it with `Actions::Reset`, which lands after a short delay and then
+/// cools down. Respawned racers get a few seconds of invulnerability, during
+/// which only falling or leaving the track sends them back, and their
+/// `RespawnComponent` tells their client what happened.
+pub struct RecoverySystem {
+  inputs: InputsReader<PlayerInput>,
+  physics_controller: PhysicsController,
+  watches: HashMap<PlayerId, Watch>,
+}
+
+impl Initializable for RecoverySystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
+    let physics_controller = inventory.get::<PhysicsController>().clone();
+
+    Self {
+      inputs,
+      physics_controller,
+      watches: HashMap::new(),
+    }
+  }
+}
+
+impl System for RecoverySystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    let (Some(players), Some(race), Some(level)) = (
+      backpack.get::<Players>(),
+      backpack.get::<Race>(),
+      backpack.get::<Level>(),
+    ) else {
+      return;
+    };
+    let bots = backpack.get::<Bots>();
+    let settings = scene
+      .query_mut::<&RecoveryComponent>()
+      .into_iter()
+      .next()
+      .map(|(_, settings)| *settings)
+      .unwrap_or_default();
+
+    let racers = players.ids();
+    self
+      .watches
+      .retain(|player_id, _| racers.contains(player_id));
+
+    for player_id in racers {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      let input = racer_input(&self.inputs, bots, &player_id);
+      let throttle = input.as_ref().map_or(0.0, |input| input.direction_vector.z);
+      let resetting = input
+        .as_ref()
+        .is_some_and(|input| input.actions.contains(&Actions::Reset));
+
+      let Ok((transform, respawn)) =
+        scene.query_one_mut::<(&TransformComponent, &mut RespawnComponent)>(player.player)
+      else {
+        continue;
+      };
+      let position = transform.translation;
+      let forward = transform.get_euler_direction().into_inner();
+
+      let watch = self.watches.entry(player_id).or_default();
+      let speed = watch.last_position.map_or(0.0, |last| {
+        (position - last).norm() / delta_time.max(f32::EPSILON)
+      });
+      watch.last_position = Some(position);
+
+      watch.reset_cooldown = (watch.reset_cooldown - delta_time).max(0.0);
+      let pressed = resetting && !watch.reset_held;
+      watch.reset_held = resetting;
+      if pressed && watch.reset_pending.is_none() && watch.reset_cooldown <= 0.0 {
+        watch.reset_pending = Some(settings.reset_delay);
+      }
+
+      let mut reset = false;
+      if let Some(left) = &mut watch.reset_pending {
+        *left -= delta_time;
+        respawn.reset_in = left.max(0.0);
+        reset = *left <= 0.0;
+      }
+
+      if reset {
+        watch.reset_pending = None;
+        watch.reset_cooldown = settings.reset_cooldown;
+        respawn.resets += 1;
+      }
+
+      // a racer just put back gets time to find their way and pick up speed,
+      // but still goes back if they fall off or leave the track
+      let invulnerable = respawn.is_invulnerable();
+      if invulnerable {
+        respawn.invulnerable = (respawn.invulnerable - delta_time).max(0.0);
+      }
+
+      let finished = race
+        .progress(&player_id)
+        .is_some_and(|progress| progress.finished.is_some());
+      let wrong_way = !invulnerable
+        && !finished
+        && speed > MIN_MOVING_SPEED
+        && track_direction(level, race, &player_id, &position)
+          .is_some_and(|direction| forward.dot(&direction) < WRONG_WAY_DOT);
+      watch.wrong_way = if wrong_way {
+        watch.wrong_way + delta_time
+      } else {
+        0.0
+      };
+
+      let stuck = !invulnerable && throttle != 0.0 && speed < settings.stuck_speed;
+      watch.stuck = if stuck { watch.stuck + delta_time } else { 0.0 };
+
+      let reason = if reset {
+        RespawnReason::Manual
+      } else if position.y < settings.kill_plane {
+        RespawnReason::KillPlane
+      } else if !level.in_bounds(&position) {
+        RespawnReason::OutOfBounds
+      } else if watch.wrong_way > settings.wrong_way_time {
+        RespawnReason::WrongWay
+      } else if watch.stuck > settings.stuck_time {
+        RespawnReason::Stuck
+      } else {
+        continue;
+      };
+
+      log::info!("respawning {:?}: {:?}", player_id, reason);
+      watch.last_position = None;
+      watch.wrong_way = 0.0;
+      watch.stuck = 0.0;
+      if watch.reset_pending.take().is_some() {
+        respawn.reset_in = 0.0;
+      }
+      if let Some(point) = last_checkpoint(level, race, &player_id) {
+        respawn_racer(
+          scene,
+          &self.physics_controller,
+          player.player,
+          &point,
+          reason,
+          settings.invulnerable_time,
+        );
+      }
+    }
+  }
+}
+
+/// Where a racer gets put back on the track.
+#[derive(Debug, Clone, Copy)]
+pub struct RespawnPoint {
+  pub position: Vector3<f32>,
+  /// Flat, unit length.
+  pub direction: Vector3<f32>,
+}
+
+/// Puts the racer down at `point`, facing down the track, at a standstill
+/// and invulnerable for `invulnerable_time` seconds.
+pub fn respawn_racer(
+  scene: &mut Scene,
+  physics_controller: &PhysicsController,
+  entity: Entity,
+  point: &RespawnPoint,
+  reason: RespawnReason,
+  invulnerable_time: f32,
+) {
+  if let Ok((transform, movement, physics, respawn)) = scene.query_one_mut::<(
+    &mut TransformComponent,
+    &mut PlayerMovementComponent,
+    &mut PhysicsComponent,
+    &mut RespawnComponent,
+  )>(entity)
+  {
+    transform.translation = point.position + Vector3::y() * RESPAWN_HEIGHT;
+    transform.rotation = Vector3::new(0.0, point.direction.x.atan2(point.direction.z), 0.0);
+    movement.current_velocity = 0.0;
+    movement.air_velocity = 0.0;
+    physics_controller.set_linvel(physics, Vector3::zeros());
+    physics_controller.set_angvel(physics, Vector3::zeros());
+
+    respawn.respawns += 1;
+    respawn.reason = reason;
+    respawn.invulnerable = invulnerable_time;
+  }
+}
+
+/// The last checkpoint the racer passed, facing the way the track goes from
+/// there.
+pub fn last_checkpoint(level: &Level, race: &Race, player_id: &PlayerId) -> Option<RespawnPoint> {
+  let count = level.checkpoints.len();
+  if count == 0 {
+    return None;
+  }
+
+  let next = race
+    .progress(player_id)
+    .map_or(1, |progress| progress.next_checkpoint)
+    % count;
+  let last = &level.checkpoints[(next + count - 1) % count];
+
+  let direction = match &level.track {
+    Some(track) => track.closest(&last.position).tangent,
+    None => level.checkpoints[next].position - last.position,
+  };
+  let direction = Vector3::new(direction.x, 0.0, direction.z)
+    .try_normalize(f32::EPSILON)
+    .unwrap_or_else(Vector3::z);
+
+  Some(RespawnPoint {
+    position: last.position,
+    direction,
+  })
+}
+
+/// Which way the racer should be going, along the track spline when the
+/// level has one and towards their next checkpoint otherwise.
+fn track_direction(
+  level: &Level,
+  race: &Race,
+  player_id: &PlayerId,
+  position: &Vector3<f32>,
+) -> Option<Vector3<f32>> {
+  if let Some(track) = &level.track {
+    return Some(track.closest(position).tangent);
+  }
+
+  let progress = race.progress(player_id)?;
+  let next = level.checkpoints.get(progress.next_checkpoint)?;
+  (next.position - position).try_normalize(f32::EPSILON)
+}
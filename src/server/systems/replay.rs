@@ -0,0 +1,124 @@
+use crate::server::players::Players;
+use crate::server::race::{Race, RaceEvent};
+use crate::server::replay::ReplayRecorder;
+use crate::shared::components::{Ability, ItemComponent};
+use crate::shared::replay::ReplayEventKind;
+
+use engine::application::scene::{Scene, TransformComponent};
+use engine::networking::connection::PlayerId;
+use engine::systems::{Backpack, Initializable, Inventory, System};
+use std::collections::HashMap;
+
+/// Feeds the `ReplayRecorder` with every racer's transform each tick, plus
+/// the checkpoints they pass, the smoke bombs they throw and where they finish.
+/// The replay is written out as soon as every racer has finished, or once it
+/// gets too long.
+pub struct ReplaySystem {
+  /// The last `ItemComponent::uses` seen for each racer.
+  item_uses: HashMap<PlayerId, u32>,
+}
+
+impl Initializable for ReplaySystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {
+      item_uses: HashMap::new(),
+    }
+  }
+}
+
+impl System for ReplaySystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let (Some(players), Some(race)) = (backpack.get::<Players>(), backpack.get::<Race>()) else {
+      return;
+    };
+    let time = race.elapsed();
+    let checkpoints: Vec<_> = race
+      .events()
+      .iter()
+      .filter_map(|event| match event {
+        RaceEvent::Checkpoint {
+          player_id,
+          checkpoint,
+          lap,
+          ..
+        } => Some((*player_id, *checkpoint, *lap)),
+        RaceEvent::LapCompleted { player_id, lap, .. } => Some((*player_id, 0, *lap)),
+        _ => None,
+      })
+      .collect();
+    let finishes: Vec<_> = race
+      .events()
+      .iter()
+      .filter_map(|event| match event {
+        RaceEvent::Finished { player_id, place } => Some((*player_id, *place)),
+        _ => None,
+      })
+      .collect();
+
+    let mut racers = vec![];
+    let mut smoke_bombs = vec![];
+    let ids = players.ids();
+    let race_over = race.is_over(&ids);
+    self
+      .item_uses
+      .retain(|player_id, _| ids.contains(player_id));
+    for player_id in ids {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      if let Ok(transform) = scene.query_one_mut::<&TransformComponent>(player.player) {
+        racers.push((
+          player_id,
+          players.username(&player_id).to_string(),
+          player.loadout.clone(),
+          transform.translation,
+          transform.rotation,
+        ));
+      }
+
+      if let Ok(item) = scene.query_one_mut::<&ItemComponent>(player.player) {
+        let seen = self.item_uses.insert(player_id, item.uses);
+        if seen.is_some_and(|seen| seen != item.uses) && item.used == Ability::SmokeBomb {
+          smoke_bombs.push(player_id);
+        }
+      }
+    }
+
+    let Some(recorder) = backpack.get_mut::<ReplayRecorder>() else {
+      return;
+    };
+    if !recorder.is_recording() {
+      return;
+    }
+    if (race_over || recorder.is_full()) && recorder.has_frames() {
+      recorder.flush();
+    }
+    // nothing worth recording while everyone looks at the results
+    if race_over {
+      return;
+    }
+
+    let transforms = racers
+      .into_iter()
+      .map(|(player_id, username, loadout, translation, rotation)| {
+        let racer = recorder.racer(player_id, &username, &loadout);
+        (racer, translation, rotation)
+      })
+      .collect();
+    recorder.record_frame(time, transforms);
+
+    for (player_id, checkpoint, lap) in checkpoints {
+      recorder.record_event(
+        time,
+        &player_id,
+        ReplayEventKind::Checkpoint { checkpoint, lap },
+      );
+    }
+    for player_id in smoke_bombs {
+      recorder.record_event(time, &player_id, ReplayEventKind::SmokeBomb);
+    }
+    for (player_id, place) in finishes {
+      recorder.record_event(time, &player_id, ReplayEventKind::Finish { place });
+    }
+  }
+}
@@ -0,0 +1,95 @@
+use crate::server::level::Level;
+use crate::server::players::Players;
+use crate::server::race::{GameMode, Race};
+use crate::shared::components::PlayerMovementComponent;
+use crate::shared::input::{Actions, PlayerInput};
+
+use engine::application::components::PhysicsComponent;
+use engine::application::scene::{Scene, TransformComponent};
+use engine::networking::connection::PlayerId;
+use engine::systems::{
+  input::InputsReader, physics::PhysicsController, Backpack, Initializable, Inventory, System,
+};
+use nalgebra::Vector3;
+use std::collections::HashSet;
+
+/// Quick restart for time trials: `Actions::Restart` puts the board back on
+/// the start line, at a standstill, and starts a fresh lap.
+pub struct TimeTrialSystem {
+  inputs: InputsReader<PlayerInput>,
+  physics_controller: PhysicsController,
+  restart_held: HashSet<PlayerId>,
+}
+
+impl Initializable for TimeTrialSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
+    let physics_controller = inventory.get::<PhysicsController>().clone();
+
+    Self {
+      inputs,
+      physics_controller,
+      restart_held: HashSet::new(),
+    }
+  }
+}
+
+impl System for TimeTrialSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let (Some(players), Some(race)) = (backpack.get::<Players>(), backpack.get::<Race>()) else {
+      return;
+    };
+    if race.mode() != GameMode::TimeTrial {
+      return;
+    }
+
+    let mut restarts = vec![];
+    for player_id in players.ids() {
+      let restarting = self
+        .inputs
+        .read_for(&player_id)
+        .is_some_and(|input| input.actions.contains(&Actions::Restart));
+      if !restarting {
+        self.restart_held.remove(&player_id);
+      } else if self.restart_held.insert(player_id)
+        && let Some(player) = players.get(&player_id)
+      {
+        restarts.push((player_id, player.player));
+      }
+    }
+
+    let level = backpack.get::<Level>().unwrap();
+    let checkpoints = level.checkpoints.len();
+    let Some(start) = level.start_transform(0) else {
+      return;
+    };
+
+    for (player_id, entity) in &restarts {
+      log::info!("{:?} restarted their lap", player_id);
+
+      if let Ok((transform, movement, physics)) = scene.query_one_mut::<(
+        &mut TransformComponent,
+        &mut PlayerMovementComponent,
+        &mut PhysicsComponent,
+      )>(*entity)
+      {
+        transform.translation = start.translation;
+        transform.rotation = start.rotation;
+        movement.current_velocity = 0.0;
+        movement.air_velocity = 0.0;
+        self
+          .physics_controller
+          .set_linvel(physics, Vector3::zeros());
+        self
+          .physics_controller
+          .set_angvel(physics, Vector3::zeros());
+      }
+    }
+
+    if let Some(race) = backpack.get_mut::<Race>() {
+      for (player_id, _) in restarts {
+        race.restart_lap(player_id, checkpoints);
+      }
+    }
+  }
+}
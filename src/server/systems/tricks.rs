@@ -0,0 +1,192 @@
+use crate::server::bots::{racer_input, Bots};
+use crate::server::players::Players;
+use crate::server::tricks::Airtime;
+use crate::shared::components::{
+  BoostMeterComponent, PlayerMovementComponent, RespawnComponent, TrickComponent,
+};
+use crate::shared::input::{Actions, PlayerInput};
+use crate::shared::movement::landing_alignment;
+
+use engine::application::components::PhysicsComponent;
+use engine::application::scene::{Scene, TransformComponent};
+use engine::networking::connection::PlayerId;
+use engine::systems::{
+  input::InputsReader, physics::PhysicsController, Backpack, Initializable, Inventory, System,
+};
+use engine::utils::units::Time;
+use nalgebra::Vector3;
+use rapier3d::prelude::*;
+use std::collections::HashMap;
+
+/// How far below a board the ground has to be for it to count as airborne,
+/// the same reach as the hover ray.
+const GROUND_DISTANCE: f32 = 1.0;
+/// Shorter hops than this many seconds, like bumps in the track, aren't
+/// jumps and don't land or bail.
+const MIN_AIRTIME: f32 = 0.2;
+
+#[derive(Debug, Default)]
+struct Rider {
+  airtime: Option<Airtime>,
+  respawns: Option<u32>,
+}
+
+/// Watches every racer's board while it's in the air and works out the
+/// spins, flips and grabs it does from how it turns. Landing square to the
+/// ground banks their points into the racer's `TrickComponent` and fills
+/// their boost meter, while landing badly, or falling and being respawned,
+/// is a bail that loses them and resets the combo.
+pub struct TrickSystem {
+  inputs: InputsReader<PlayerInput>,
+  physics_controller: PhysicsController,
+  riders: HashMap<PlayerId, Rider>,
+}
+
+impl Initializable for TrickSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let inputs = inventory.get::<InputsReader<PlayerInput>>().clone();
+    let physics_controller = inventory.get::<PhysicsController>().clone();
+
+    Self {
+      inputs,
+      physics_controller,
+      riders: HashMap::new(),
+    }
+  }
+}
+
+impl System for TrickSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    let Some(players) = backpack.get::<Players>() else {
+      return;
+    };
+    let bots = backpack.get::<Bots>();
+
+    let racers = players.ids();
+    self
+      .riders
+      .retain(|player_id, _| racers.contains(player_id));
+
+    for player_id in racers {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      let grabbing = racer_input(&self.inputs, bots, &player_id)
+        .is_some_and(|input| input.actions.contains(&Actions::Grab));
+
+      let Ok((transform, physics, movement, respawn, tricks, meter)) = scene.query_one_mut::<(
+        &TransformComponent,
+        &PhysicsComponent,
+        &PlayerMovementComponent,
+        Option<&RespawnComponent>,
+        &mut TrickComponent,
+        Option<&mut BoostMeterComponent>,
+      )>(player.player) else {
+        continue;
+      };
+      let rider = self.riders.entry(player_id).or_default();
+
+      // put back on the track, so whatever was going on in the air is lost
+      let respawns = respawn.map(|respawn| respawn.respawns);
+      if rider.respawns != respawns {
+        let respawned = rider.respawns.is_some();
+        rider.respawns = respawns;
+        if respawned && rider.airtime.take().is_some() {
+          bail(tricks);
+        }
+        continue;
+      }
+
+      let Some(normal) = ground_normal(&self.physics_controller, physics, transform.translation)
+      else {
+        let airtime = rider
+          .airtime
+          .get_or_insert_with(|| Airtime::new(transform.rotation));
+        airtime.update(transform.rotation, grabbing, delta_time);
+
+        let points = airtime
+          .tricks()
+          .iter()
+          .map(|trick| trick.points)
+          .sum::<u32>()
+          * (tricks.combo + 1);
+        if tricks.pending != points {
+          tricks.pending = points;
+        }
+        continue;
+      };
+
+      let Some(airtime) = rider.airtime.take() else {
+        continue;
+      };
+      if airtime.time < MIN_AIRTIME {
+        if tricks.pending != 0 {
+          tricks.pending = 0;
+        }
+        continue;
+      }
+
+      let clean =
+        landing_alignment(transform, &normal) >= movement.landing_tolerance && !airtime.mid_flip();
+      if clean {
+        land(tricks, meter, &airtime);
+      } else {
+        bail(tricks);
+      }
+    }
+  }
+}
+
+/// Banks the tricks from the jump, if there were any, and otherwise ends the
+/// combo.
+fn land(tricks: &mut TrickComponent, meter: Option<&mut BoostMeterComponent>, airtime: &Airtime) {
+  let done = airtime.tricks();
+  if tricks.pending != 0 {
+    tricks.pending = 0;
+  }
+  if done.is_empty() {
+    if tricks.combo != 0 {
+      tricks.combo = 0;
+    }
+    return;
+  }
+
+  tricks.combo += 1;
+  let points = done.iter().map(|trick| trick.points).sum::<u32>() * tricks.combo;
+  tricks.score += points;
+  tricks.landed += 1;
+  tricks.last_points = points;
+  tricks.last_tricks = done
+    .iter()
+    .map(|trick| trick.name.as_str())
+    .collect::<Vec<_>>()
+    .join(" + ");
+
+  if let Some(meter) = meter {
+    meter.fill(meter.trick_fill * done.len() as f32);
+  }
+}
+
+fn bail(tricks: &mut TrickComponent) {
+  tricks.pending = 0;
+  tricks.combo = 0;
+  tricks.bails += 1;
+}
+
+/// The normal of the ground under the board, if it's close enough to hover
+/// over. Looks straight down, as the server doesn't know which way is down
+/// for the board the way its client does.
+fn ground_normal(
+  physics_controller: &PhysicsController,
+  physics: &PhysicsComponent,
+  position: Vector3<f32>,
+) -> Option<Vector3<f32>> {
+  let rigidbody_handle = physics_controller.get_rigid_body(&physics.joint.body.id)?;
+  let ray = Ray::new(position.into(), -Vector3::y());
+  let filter = QueryFilter::default().exclude_rigid_body(rigidbody_handle);
+
+  physics_controller
+    .raycast(&ray, GROUND_DISTANCE, true, filter)
+    .map(|(_, _, intersection)| intersection.normal)
+}
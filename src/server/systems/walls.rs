@@ -0,0 +1,170 @@
+use crate::server::players::Players;
+use crate::shared::components::{WallContactComponent, WallContactKind};
+
+use engine::application::components::PhysicsComponent;
+use engine::application::scene::{Scene, TransformComponent};
+use engine::networking::connection::PlayerId;
+use engine::systems::{physics::PhysicsController, Backpack, Initializable, Inventory, System};
+use engine::utils::units::Time;
+use nalgebra::Vector3;
+use rapier3d::prelude::*;
+use std::collections::HashMap;
+
+/// From the board's center to its nose, and to its sides.
+const BOARD_HALF_LENGTH: f32 = 1.0;
+const BOARD_HALF_WIDTH: f32 = 0.75;
+/// How close past the board's edges a wall counts as touching it.
+const CONTACT_MARGIN: f32 = 0.25;
+/// Surfaces facing further up than this, out of 1, are floor and ramps
+/// rather than walls.
+const MAX_WALL_UP: f32 = 0.7;
+/// Seconds between two scrape contacts while a board runs along a wall.
+const SCRAPE_INTERVAL: f32 = 0.1;
+
+#[derive(Debug, Default)]
+struct Contact {
+  last_position: Option<Vector3<f32>>,
+  /// Whether the board was already against a wall last tick, so driving
+  /// into one is only an impact once.
+  touching: bool,
+  /// Seconds of scraping not yet sent as a contact.
+  scraping: f32,
+}
+
+/// Looks out from every racer's board, the way it's moving and to its
+/// sides, for the static walls of the track's collision mesh. Driving into
+/// one, or along it, fills in the racer's `WallContactComponent` with where
+/// and how hard they hit it; the board's movement then takes the speed off.
+pub struct WallSystem {
+  physics_controller: PhysicsController,
+  contacts: HashMap<PlayerId, Contact>,
+}
+
+impl Initializable for WallSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let physics_controller = inventory.get::<PhysicsController>().clone();
+
+    Self {
+      physics_controller,
+      contacts: HashMap::new(),
+    }
+  }
+}
+
+impl System for WallSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+    let Some(players) = backpack.get::<Players>() else {
+      return;
+    };
+
+    let racers = players.ids();
+    self
+      .contacts
+      .retain(|player_id, _| racers.contains(player_id));
+
+    for player_id in racers {
+      let Some(player) = players.get(&player_id) else {
+        continue;
+      };
+      let Ok((transform, physics, wall)) = scene.query_one_mut::<(
+        &TransformComponent,
+        &PhysicsComponent,
+        &mut WallContactComponent,
+      )>(player.player) else {
+        continue;
+      };
+
+      let contact = self.contacts.entry(player_id).or_default();
+      let position = transform.translation;
+      let velocity = contact.last_position.map_or(Vector3::zeros(), |last| {
+        (position - last) / delta_time.max(f32::EPSILON)
+      });
+      contact.last_position = Some(position);
+
+      let flat = Vector3::new(velocity.x, 0.0, velocity.z);
+      let speed = flat.norm();
+      let Some(heading) = flat
+        .try_normalize(f32::EPSILON)
+        .filter(|_| speed >= wall.min_speed)
+      else {
+        contact.touching = false;
+        contact.scraping = 0.0;
+        continue;
+      };
+
+      let Some((point, normal)) = closest_wall(
+        &self.physics_controller,
+        physics,
+        &position,
+        &heading,
+        speed * delta_time,
+      ) else {
+        contact.touching = false;
+        contact.scraping = 0.0;
+        continue;
+      };
+
+      // 0 running along the wall, PI / 2 straight into it
+      let angle = (-heading.dot(&normal)).clamp(0.0, 1.0).asin();
+      let (kind, speed_loss) = if angle >= wall.impact_angle {
+        if contact.touching {
+          continue;
+        }
+        contact.touching = true;
+        (WallContactKind::Impact, wall.max_impact_loss * angle.sin())
+      } else {
+        contact.touching = true;
+        contact.scraping += delta_time;
+        if contact.scraping < SCRAPE_INTERVAL {
+          continue;
+        }
+        let scraped = std::mem::take(&mut contact.scraping);
+        (WallContactKind::Scrape, wall.scrape_loss * scraped)
+      };
+
+      wall.contacts += 1;
+      wall.kind = kind;
+      wall.point = point;
+      wall.normal = normal;
+      wall.speed_loss = speed_loss.clamp(0.0, 1.0);
+    }
+  }
+}
+
+/// The nearest wall ahead of the board, reaching as far as it'll move this
+/// tick, or right beside it.
+fn closest_wall(
+  physics_controller: &PhysicsController,
+  physics: &PhysicsComponent,
+  position: &Vector3<f32>,
+  heading: &Vector3<f32>,
+  travel: f32,
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+  let rigidbody_handle = physics_controller.get_rigid_body(&physics.joint.body.id)?;
+  // only the static track, not other boards
+  let filter = QueryFilter::default()
+    .exclude_dynamic()
+    .exclude_rigid_body(rigidbody_handle);
+
+  let side = heading.cross(&Vector3::y());
+  let rays = [
+    (*heading, BOARD_HALF_LENGTH + CONTACT_MARGIN + travel),
+    (side, BOARD_HALF_WIDTH + CONTACT_MARGIN),
+    (-side, BOARD_HALF_WIDTH + CONTACT_MARGIN),
+  ];
+
+  rays
+    .iter()
+    .filter_map(|(direction, reach)| {
+      let ray = Ray::new((*position).into(), *direction);
+      let (_, _, intersection) = physics_controller.raycast(&ray, *reach, true, filter)?;
+      let normal = intersection.normal;
+      (normal.y.abs() < MAX_WALL_UP).then(|| {
+        let point: Vector3<f32> = ray.point_at(intersection.toi).coords;
+        (intersection.toi, point, normal)
+      })
+    })
+    .min_by(|(a, ..), (b, ..)| a.total_cmp(b))
+    .map(|(_, point, normal)| (point, normal))
+}
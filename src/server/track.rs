@@ -0,0 +1,389 @@
+use nalgebra::Vector3;
+
+use crate::shared::components::{TrackSplineComponent, TrackSplinePoint};
+
+/// Curve samples taken between two control points. Queries walk the samples
+/// as a polyline, which is plenty at the spacing tracks are authored with.
+const SAMPLES_PER_SEGMENT: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+  position: Vector3<f32>,
+  /// Along the track from the first control point.
+  distance: f32,
+  width: f32,
+  banking: f32,
+}
+
+/// Where something is relative to the track.
+#[derive(Debug, Clone, Copy)]
+pub struct TrackPosition {
+  /// Along the track from its first control point.
+  pub distance: f32,
+  /// Closest point on the racing line.
+  pub point: Vector3<f32>,
+  /// Unit vector in the driving direction.
+  pub tangent: Vector3<f32>,
+  /// Unit vector across the track, pointing to the right of `tangent`.
+  pub right: Vector3<f32>,
+  /// How far from the racing line, along `right`. Negative is to the left.
+  pub lateral_offset: f32,
+  pub width: f32,
+  pub banking: f32,
+}
+
+impl TrackPosition {
+  pub fn on_track(&self) -> bool {
+    self.lateral_offset.abs() <= self.width / 2.0
+  }
+}
+
+/// A level's `TrackSplineComponent`, sampled into a polyline that can answer
+/// where along the track a point in the world is.
+#[derive(Debug, Clone)]
+pub struct TrackSpline {
+  samples: Vec<Sample>,
+  length: f32,
+  closed: bool,
+}
+
+impl TrackSpline {
+  /// Builds a Catmull-Rom curve through the control points. Needs at least
+  /// two of them.
+  pub fn new(spline: &TrackSplineComponent) -> Option<Self> {
+    let points = &spline.points;
+    if points.len() < 2 {
+      return None;
+    }
+
+    let count = points.len();
+    let point = |index: isize| -> TrackSplinePoint {
+      if spline.closed {
+        points[index.rem_euclid(count as isize) as usize]
+      } else {
+        points[index.clamp(0, count as isize - 1) as usize]
+      }
+    };
+    let segments = if spline.closed { count } else { count - 1 };
+
+    let mut samples: Vec<Sample> = vec![];
+    for segment in 0..segments as isize {
+      let (p0, p1, p2, p3) = (
+        point(segment - 1),
+        point(segment),
+        point(segment + 1),
+        point(segment + 2),
+      );
+      for step in 0..SAMPLES_PER_SEGMENT {
+        let t = step as f32 / SAMPLES_PER_SEGMENT as f32;
+        samples.push(Sample {
+          position: catmull_rom(p0.position, p1.position, p2.position, p3.position, t),
+          distance: 0.0,
+          width: lerp(p1.width, p2.width, t),
+          banking: lerp(p1.banking, p2.banking, t),
+        });
+      }
+    }
+    if !spline.closed {
+      let last = point(count as isize - 1);
+      samples.push(Sample {
+        position: last.position,
+        distance: 0.0,
+        width: last.width,
+        banking: last.banking,
+      });
+    }
+
+    let mut length = 0.0;
+    for index in 1..samples.len() {
+      length += (samples[index].position - samples[index - 1].position).norm();
+      samples[index].distance = length;
+    }
+    if spline.closed {
+      length += (samples[0].position - samples[samples.len() - 1].position).norm();
+    }
+
+    Some(Self {
+      samples,
+      length,
+      closed: spline.closed,
+    })
+  }
+
+  pub fn length(&self) -> f32 {
+    self.length
+  }
+
+  pub fn is_closed(&self) -> bool {
+    self.closed
+  }
+
+  /// The closest point on the track to `position`, and where `position` is
+  /// relative to it.
+  pub fn closest(&self, position: &Vector3<f32>) -> TrackPosition {
+    let mut best = (f32::MAX, 0, 0.0);
+    for segment in 0..self.segment_count() {
+      let (start, end) = self.segment(segment);
+      let t = project(&start.position, &end.position, position);
+      let distance = (start.position.lerp(&end.position, t) - position).norm_squared();
+      if distance < best.0 {
+        best = (distance, segment, t);
+      }
+    }
+
+    let (_, segment, t) = best;
+    let mut track_position = self.on_segment(segment, t);
+    track_position.lateral_offset = (position - track_position.point).dot(&track_position.right);
+    track_position
+  }
+
+  /// How far along the track `position` is, from its first control point.
+  pub fn distance_along(&self, position: &Vector3<f32>) -> f32 {
+    self.closest(position).distance
+  }
+
+  /// How far `position` is from the racing line. Negative is to the left.
+  pub fn lateral_offset(&self, position: &Vector3<f32>) -> f32 {
+    self.closest(position).lateral_offset
+  }
+
+  /// The racing line at `distance` along the track. Closed tracks wrap
+  /// around, open ones stop at their ends.
+  pub fn at(&self, distance: f32) -> TrackPosition {
+    let distance = if self.closed {
+      distance.rem_euclid(self.length)
+    } else {
+      distance.clamp(0.0, self.length)
+    };
+
+    let segment = self
+      .samples
+      .partition_point(|sample| sample.distance <= distance)
+      .saturating_sub(1)
+      .min(self.segment_count() - 1);
+    let start = self.samples[segment];
+    let span = self.segment_length(segment);
+    let t = if span > f32::EPSILON {
+      ((distance - start.distance) / span).clamp(0.0, 1.0)
+    } else {
+      0.0
+    };
+
+    self.on_segment(segment, t)
+  }
+
+  /// Driving direction at `distance` along the track.
+  pub fn tangent(&self, distance: f32) -> Vector3<f32> {
+    self.at(distance).tangent
+  }
+
+  /// How far ahead `to` is of `from`, both distances along the track.
+  /// Negative when `to` is behind. On closed tracks this takes the short way
+  /// around, so it's never more than half a lap either way.
+  pub fn delta(&self, from: f32, to: f32) -> f32 {
+    let delta = to - from;
+    if !self.closed {
+      return delta;
+    }
+
+    let half = self.length / 2.0;
+    (delta + half).rem_euclid(self.length) - half
+  }
+
+  fn segment_count(&self) -> usize {
+    if self.closed {
+      self.samples.len()
+    } else {
+      self.samples.len() - 1
+    }
+  }
+
+  fn segment(&self, segment: usize) -> (&Sample, &Sample) {
+    (
+      &self.samples[segment],
+      &self.samples[(segment + 1) % self.samples.len()],
+    )
+  }
+
+  fn segment_length(&self, segment: usize) -> f32 {
+    let (start, end) = self.segment(segment);
+    (end.position - start.position).norm()
+  }
+
+  fn on_segment(&self, segment: usize, t: f32) -> TrackPosition {
+    let (start, end) = self.segment(segment);
+    let tangent = (end.position - start.position)
+      .try_normalize(f32::EPSILON)
+      .unwrap_or_else(Vector3::z);
+    let right = tangent
+      .cross(&Vector3::y())
+      .try_normalize(f32::EPSILON)
+      .unwrap_or_else(Vector3::x);
+
+    TrackPosition {
+      distance: start.distance + self.segment_length(segment) * t,
+      point: start.position.lerp(&end.position, t),
+      tangent,
+      right,
+      lateral_offset: 0.0,
+      width: lerp(start.width, end.width, t),
+      banking: lerp(start.banking, end.banking, t),
+    }
+  }
+}
+
+/// How far along `start`-`end` the closest point to `position` is, out of 1.
+fn project(start: &Vector3<f32>, end: &Vector3<f32>, position: &Vector3<f32>) -> f32 {
+  let span = end - start;
+  let length = span.norm_squared();
+  if length <= f32::EPSILON {
+    return 0.0;
+  }
+  ((position - start).dot(&span) / length).clamp(0.0, 1.0)
+}
+
+fn catmull_rom(
+  p0: Vector3<f32>,
+  p1: Vector3<f32>,
+  p2: Vector3<f32>,
+  p3: Vector3<f32>,
+  t: f32,
+) -> Vector3<f32> {
+  let t2 = t * t;
+  let t3 = t2 * t;
+
+  (p1 * 2.0
+    + (p2 - p0) * t
+    + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+    + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+    * 0.5
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+  from + (to - from) * t
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn spline(points: &[[f32; 3]], closed: bool) -> TrackSpline {
+    TrackSpline::new(&TrackSplineComponent {
+      points: points
+        .iter()
+        .map(|point| TrackSplinePoint {
+          position: Vector3::from(*point),
+          width: 12.0,
+          banking: 0.0,
+        })
+        .collect(),
+      closed,
+    })
+    .unwrap()
+  }
+
+  fn straight() -> TrackSpline {
+    spline(
+      &[[0.0, 0.0, 0.0], [0.0, 0.0, 10.0], [0.0, 0.0, 20.0]],
+      false,
+    )
+  }
+
+  fn square() -> TrackSpline {
+    spline(
+      &[
+        [0.0, 0.0, 0.0],
+        [0.0, 0.0, 50.0],
+        [50.0, 0.0, 50.0],
+        [50.0, 0.0, 0.0],
+      ],
+      true,
+    )
+  }
+
+  #[test]
+  fn width_and_banking_follow_the_control_points() {
+    let track = TrackSpline::new(&TrackSplineComponent {
+      points: [(0.0, 8.0, 0.0), (10.0, 12.0, 0.2), (20.0, 20.0, 0.4)]
+        .iter()
+        .map(|(z, width, banking)| TrackSplinePoint {
+          position: Vector3::new(0.0, 0.0, *z),
+          width: *width,
+          banking: *banking,
+        })
+        .collect(),
+      closed: false,
+    })
+    .unwrap();
+
+    for (distance, width, banking) in [(0.0, 8.0, 0.0), (10.0, 12.0, 0.2), (20.0, 20.0, 0.4)] {
+      let position = track.at(distance);
+      assert!((position.width - width).abs() < 1e-3);
+      assert!((position.banking - banking).abs() < 1e-3);
+    }
+
+    let between = track.at(15.0);
+    assert!(between.width > 12.0 && between.width < 20.0);
+    assert!(between.banking > 0.2 && between.banking < 0.4);
+
+    // the same distance off the line is on the wide end and off the narrow one
+    assert!(track.closest(&Vector3::new(6.0, 0.0, 19.0)).on_track());
+    assert!(!track.closest(&Vector3::new(6.0, 0.0, 1.0)).on_track());
+  }
+
+  #[test]
+  fn straight_track_runs_through_its_points() {
+    let track = straight();
+    assert!((track.length() - 20.0).abs() < 1e-3);
+
+    let position = track.closest(&Vector3::new(0.0, 0.0, 10.0));
+    assert!((position.distance - 10.0).abs() < 1e-3);
+    assert!((position.tangent - Vector3::z()).norm() < 1e-3);
+  }
+
+  #[test]
+  fn closest_measures_how_far_off_the_line() {
+    let track = straight();
+
+    let near = track.closest(&Vector3::new(3.0, 0.0, 5.0));
+    assert!((near.distance - 5.0).abs() < 1e-3);
+    assert!((near.lateral_offset.abs() - 3.0).abs() < 1e-3);
+    assert!(near.on_track());
+
+    let far = track.closest(&Vector3::new(-8.0, 0.0, 5.0));
+    assert!((far.lateral_offset.abs() - 8.0).abs() < 1e-3);
+    assert!(!far.on_track());
+    assert!(near.lateral_offset.signum() != far.lateral_offset.signum());
+  }
+
+  #[test]
+  fn open_tracks_stop_at_their_ends() {
+    let track = straight();
+    assert!((track.at(25.0).distance - 20.0).abs() < 1e-3);
+    assert!(track.at(-5.0).distance.abs() < 1e-3);
+    assert!((track.delta(15.0, 5.0) + 10.0).abs() < 1e-3);
+  }
+
+  #[test]
+  fn closed_tracks_wrap_around() {
+    let track = square();
+    let length = track.length();
+    assert!(track.is_closed());
+
+    assert!((track.at(length + 1.0).distance - 1.0).abs() < 1e-3);
+    assert!((track.delta(length - 1.0, 1.0) - 2.0).abs() < 1e-3);
+    assert!((track.delta(1.0, length - 1.0) + 2.0).abs() < 1e-3);
+  }
+
+  #[test]
+  fn at_and_closest_agree() {
+    let track = square();
+    for step in 0..20 {
+      let distance = track.length() * step as f32 / 20.0;
+      let point = track.at(distance).point;
+      let closest = track.closest(&point);
+      assert!((closest.point - point).norm() < 1e-3);
+      assert!(closest.lateral_offset.abs() < 1e-3);
+    }
+  }
+}
@@ -0,0 +1,208 @@
+use nalgebra::{Rotation3, Vector3};
+use std::f32::consts::{PI, TAU};
+
+/// Points per half turn of spin.
+const SPIN_POINTS: u32 = 100;
+/// Points per full flip.
+const FLIP_POINTS: u32 = 250;
+/// Points for any grab, plus `GRAB_POINTS_PER_SECOND` while it's held.
+const GRAB_POINTS: u32 = 50;
+const GRAB_POINTS_PER_SECOND: f32 = 100.0;
+/// Grabs shorter than this many seconds don't count.
+const MIN_GRAB_TIME: f32 = 0.3;
+/// How many radians short of a full rotation still count as one, for spins
+/// and flips alike.
+const ROTATION_TOLERANCE: f32 = 0.6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trick {
+  pub name: String,
+  pub points: u32,
+}
+
+/// How the board turned during one jump, from its rotation every tick.
+#[derive(Debug, Clone)]
+pub struct Airtime {
+  last_rotation: Rotation3<f32>,
+  /// Radians turned around the board's up axis.
+  spin: f32,
+  /// Radians turned nose over tail around the board's right axis, positive
+  /// nose down.
+  flip: f32,
+  grab: f32,
+  pub time: f32,
+}
+
+impl Airtime {
+  pub fn new(rotation: Vector3<f32>) -> Self {
+    Self {
+      last_rotation: orientation(rotation),
+      spin: 0.0,
+      flip: 0.0,
+      grab: 0.0,
+      time: 0.0,
+    }
+  }
+
+  /// Adds up how far the board turned since the last tick about its own up
+  /// and right axes. This works from the whole change in orientation rather
+  /// than from Euler angles, whose yaw and roll jump by half a turn once the
+  /// nose goes past straight up or down.
+  pub fn update(&mut self, rotation: Vector3<f32>, grabbing: bool, delta_time: f32) {
+    let rotation = orientation(rotation);
+    let turn = (rotation * self.last_rotation.inverse()).scaled_axis();
+    self.spin += turn.dot(&(rotation * Vector3::y()));
+    self.flip += turn.dot(&(rotation * Vector3::x()));
+    if grabbing {
+      self.grab += delta_time;
+    }
+    self.time += delta_time;
+    self.last_rotation = rotation;
+  }
+
+  /// Whether the board is coming down upside down or on its nose or tail,
+  /// however square its underside is to the ground.
+  pub fn mid_flip(&self) -> bool {
+    let off = self.flip.abs() % TAU;
+    off.min(TAU - off) > ROTATION_TOLERANCE
+  }
+
+  pub fn tricks(&self) -> Vec<Trick> {
+    let mut tricks = vec![];
+
+    let flips = ((self.flip.abs() + ROTATION_TOLERANCE) / TAU) as u32;
+    if flips > 0 {
+      let flip = if self.flip > 0.0 {
+        "Frontflip"
+      } else {
+        "Backflip"
+      };
+      let name = match flips {
+        1 => flip.to_string(),
+        2 => format!("Double {}", flip),
+        3 => format!("Triple {}", flip),
+        _ => format!("{}x {}", flips, flip),
+      };
+      tricks.push(Trick {
+        name,
+        points: FLIP_POINTS * flips,
+      });
+    }
+
+    let half_turns = ((self.spin.abs() + ROTATION_TOLERANCE) / PI) as u32;
+    if half_turns > 0 {
+      tricks.push(Trick {
+        name: (half_turns * 180).to_string(),
+        points: SPIN_POINTS * half_turns,
+      });
+    }
+
+    if self.grab >= MIN_GRAB_TIME {
+      tricks.push(Trick {
+        name: "Grab".to_string(),
+        points: GRAB_POINTS + (self.grab * GRAB_POINTS_PER_SECOND) as u32,
+      });
+    }
+
+    tricks
+  }
+}
+
+fn orientation(rotation: Vector3<f32>) -> Rotation3<f32> {
+  Rotation3::from_euler_angles(rotation.x, rotation.y, rotation.z)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const STEPS: u32 = 60;
+  const TICK: f32 = 1.0 / 60.0;
+
+  fn euler(rotation: Rotation3<f32>) -> Vector3<f32> {
+    let (x, y, z) = rotation.euler_angles();
+    Vector3::new(x, y, z)
+  }
+
+  /// Turns the board by `angle` radians around its own `axis` over one
+  /// second of airtime.
+  fn jump(start: Rotation3<f32>, axis: Vector3<f32>, angle: f32, grabbing: bool) -> Airtime {
+    let mut airtime = Airtime::new(euler(start));
+    for step in 1..=STEPS {
+      let turn = Rotation3::from_scaled_axis(axis * angle * step as f32 / STEPS as f32);
+      airtime.update(euler(start * turn), grabbing, TICK);
+    }
+    airtime
+  }
+
+  #[test]
+  fn a_straight_jump_scores_nothing() {
+    let airtime = jump(Rotation3::identity(), Vector3::y(), 0.0, false);
+    assert!(airtime.tricks().is_empty());
+    assert!(!airtime.mid_flip());
+  }
+
+  #[test]
+  fn spins_score_per_half_turn() {
+    let airtime = jump(Rotation3::identity(), Vector3::y(), TAU, false);
+    assert_eq!(
+      airtime.tricks(),
+      vec![Trick {
+        name: "360".to_string(),
+        points: SPIN_POINTS * 2,
+      }]
+    );
+  }
+
+  #[test]
+  fn nearly_full_turns_still_count() {
+    let airtime = jump(Rotation3::identity(), -Vector3::y(), PI - 0.5, false);
+    assert_eq!(airtime.tricks()[0].name, "180");
+
+    let airtime = jump(Rotation3::identity(), Vector3::y(), PI - 1.0, false);
+    assert!(airtime.tricks().is_empty());
+  }
+
+  #[test]
+  fn flips_through_vertical_are_counted_whole() {
+    // pitching the nose through straight up flips the Euler yaw and roll
+    // around, which must not show up as a spin
+    let start = Rotation3::from_euler_angles(0.0, 0.7, 0.0);
+    let airtime = jump(start, -Vector3::x(), TAU, false);
+    assert_eq!(
+      airtime.tricks(),
+      vec![Trick {
+        name: "Backflip".to_string(),
+        points: FLIP_POINTS,
+      }]
+    );
+    assert!(!airtime.mid_flip());
+
+    let airtime = jump(start, Vector3::x(), 2.0 * TAU, false);
+    assert_eq!(airtime.tricks()[0].name, "Double Frontflip");
+  }
+
+  #[test]
+  fn half_a_flip_lands_mid_flip() {
+    let airtime = jump(Rotation3::identity(), Vector3::x(), PI, false);
+    assert!(airtime.mid_flip());
+    assert!(airtime.tricks().is_empty());
+  }
+
+  #[test]
+  fn grabs_score_by_how_long_they_are_held() {
+    let mut airtime = Airtime::new(Vector3::zeros());
+    airtime.update(Vector3::zeros(), true, 1.0);
+    assert_eq!(
+      airtime.tricks(),
+      vec![Trick {
+        name: "Grab".to_string(),
+        points: GRAB_POINTS + GRAB_POINTS_PER_SECOND as u32,
+      }]
+    );
+
+    let mut airtime = Airtime::new(Vector3::zeros());
+    airtime.update(Vector3::zeros(), true, MIN_GRAB_TIME / 2.0);
+    assert!(airtime.tricks().is_empty());
+  }
+}
@@ -0,0 +1,38 @@
+use engine::renderer::resources::animation::AnimationId;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+use crate::shared::components::BoardState;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Schema)]
+pub struct StateAnimation {
+  pub state: BoardState,
+  /// Clip to play. It has to be one of the entries in the avatar's
+  /// `AnimationComponent`.
+  pub animation: AnimationId,
+  #[schema(default = "1.0")]
+  pub rate: f32,
+  /// How long it takes, in seconds, to blend into this clip.
+  #[schema(default = "0.25")]
+  pub blend_time: f32,
+}
+
+/// Maps board states to the avatar's animation clips. States without an entry
+/// fall back to the closest state that has one (see `BoardState::fallback`).
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema)]
+pub struct AnimationControllerComponent {
+  pub states: Vec<StateAnimation>,
+}
+
+impl AnimationControllerComponent {
+  pub fn animation_for(&self, state: BoardState) -> Option<&StateAnimation> {
+    let mut state = Some(state);
+    while let Some(current) = state {
+      if let Some(animation) = self.states.iter().find(|entry| entry.state == current) {
+        return Some(animation);
+      }
+      state = current.fallback();
+    }
+    None
+  }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+use crate::shared::components::PlayerMovementComponent;
+
+/// Stat profile of a board prefab. Every `Board` prefab in the level is part
+/// of the catalog players pick from, and its stats replace the rider's
+/// movement settings when it's equipped.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct BoardComponent {
+  #[schema(default = "2000.0")]
+  pub max_velocity: f32,
+  #[schema(default = "200.0")]
+  pub acceleration: f32,
+  #[schema(default = "110.0")]
+  pub rotation_speed: f32,
+  #[schema(default = "0.35")]
+  pub hover_height: f32,
+}
+
+impl BoardComponent {
+  pub fn apply(&self, movement: &mut PlayerMovementComponent) {
+    movement.max_velocity = self.max_velocity;
+    movement.acceleration = self.acceleration;
+    movement.rotation_speed = self.rotation_speed;
+    movement.max_height_from_surface = self.hover_height;
+  }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// A racer's boost meter, filled by drifting, tricks and pickups and spent by
+/// holding `Actions::Boost`. While `active`, the board accelerates
+/// `acceleration_multiplier` times faster up to `speed_multiplier` times its
+/// top speed. The server keeps `amount` and `active` up to date, the rest is
+/// tuning set on the avatar prefab.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct BoostMeterComponent {
+  #[schema(default = "0.0")]
+  pub amount: f32,
+  #[schema(default = "false")]
+  pub active: bool,
+
+  #[schema(default = "100.0")]
+  pub capacity: f32,
+  /// Filled per second of drifting.
+  #[schema(default = "15.0")]
+  pub drift_fill: f32,
+  /// Filled per trick landed.
+  #[schema(default = "20.0")]
+  pub trick_fill: f32,
+  /// Filled per pickup collected.
+  #[schema(default = "30.0")]
+  pub pickup_fill: f32,
+  /// Spent per second of boosting.
+  #[schema(default = "35.0")]
+  pub drain: f32,
+  #[schema(default = "1.5")]
+  pub acceleration_multiplier: f32,
+  #[schema(default = "1.25")]
+  pub speed_multiplier: f32,
+}
+
+impl BoostMeterComponent {
+  pub fn fill(&mut self, amount: f32) {
+    self.amount = (self.amount + amount).clamp(0.0, self.capacity);
+  }
+
+  /// How full the meter is, out of 1.
+  pub fn ratio(&self) -> f32 {
+    if self.capacity <= 0.0 {
+      return 0.0;
+    }
+    self.amount / self.capacity
+  }
+}
@@ -0,0 +1,31 @@
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// A box on the track that boosts boards driving over it the right way.
+/// While boosted, a board's top speed is raised to `strength` times its
+/// board's `max_velocity`, then eases back once `duration` runs out.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct BoostPadComponent {
+  /// Half the size of the pad along each of its axes.
+  pub half_extents: Vector3<f32>,
+  /// Which way the pad pushes, in the pad's own axes. Boards crossing it the
+  /// other way aren't boosted.
+  pub direction: Vector3<f32>,
+  #[schema(default = "1.5")]
+  pub strength: f32,
+  /// Seconds at full boost after leaving the pad.
+  #[schema(default = "1.5")]
+  pub duration: f32,
+}
+
+/// A box that changes the top speed of boards inside it, like sand or grass
+/// slowing them down. Where zones overlap the slowest one wins.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct SpeedZoneComponent {
+  /// Half the size of the zone along each of its axes.
+  pub half_extents: Vector3<f32>,
+  /// Multiplies the board's `max_velocity` while inside.
+  #[schema(default = "0.6")]
+  pub multiplier: f32,
+}
@@ -0,0 +1,35 @@
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// How a racer's board reacts to hitting another one. The server fills in
+/// the last bump, and `bumps` goes up by one on every bump so the board's
+/// movement, sounds and effects can tell a new one apart from the last one.
+/// `rams` counts the bumps this racer caused by driving into someone.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct BumpComponent {
+  #[schema(default = "0")]
+  pub bumps: u32,
+  /// Velocity change from the last bump, in world space.
+  pub impulse: Vector3<f32>,
+  /// Seconds the last bump took control away for.
+  #[schema(default = "0.0")]
+  pub stun: f32,
+  /// Closing speed of the last bump, for how loud and big it looks.
+  #[schema(default = "0.0")]
+  pub speed: f32,
+  #[schema(default = "0")]
+  pub rams: u32,
+
+  /// Boards closing slower than this just touch.
+  #[schema(default = "3.0")]
+  pub min_speed: f32,
+  /// Bump velocity per unit of closing speed.
+  #[schema(default = "0.6")]
+  pub impulse_per_speed: f32,
+  #[schema(default = "12.0")]
+  pub max_impulse: f32,
+  /// Seconds without control after the hardest bump, less for softer ones.
+  #[schema(default = "0.6")]
+  pub stun_time: f32,
+}
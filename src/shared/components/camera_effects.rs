@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+use crate::shared::components::BoardState;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize, Schema)]
+pub enum Easing {
+  #[default]
+  Linear,
+  EaseIn,
+  EaseOut,
+  EaseInOut,
+}
+
+impl Easing {
+  /// Maps `t` in `[0, 1]` onto the curve.
+  pub fn apply(&self, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match self {
+      Self::Linear => t,
+      Self::EaseIn => t * t,
+      Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+      Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Schema)]
+pub struct Shake {
+  /// Largest offset, in world units, the camera is pushed around by.
+  #[schema(default = "0.1")]
+  pub intensity: f32,
+  /// Seconds it takes for the shake to die out.
+  #[schema(default = "0.3")]
+  pub duration: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Schema)]
+pub struct StateShake {
+  pub state: BoardState,
+  pub shake: Shake,
+}
+
+/// Camera reactions to how the board is doing: the field of view widens with
+/// speed, and the camera shakes when the board enters some states, loses a lot
+/// of speed at once or starts boosting.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema)]
+pub struct CameraEffectsComponent {
+  /// Fraction of `max_velocity` at which the field of view starts widening.
+  #[schema(default = "0.3")]
+  pub fov_start_speed: f32,
+  /// Radians added to the field of view at `max_velocity`.
+  #[schema(default = "0.25")]
+  pub fov_widening: f32,
+  pub fov_easing: Easing,
+  /// How quickly the field of view follows speed changes.
+  #[schema(default = "4.0")]
+  pub fov_damping: f32,
+
+  /// Shakes started when the board enters a given state.
+  pub state_shakes: Vec<StateShake>,
+  /// Fraction of `max_velocity` lost in a single frame that counts as an
+  /// impact.
+  #[schema(default = "0.2")]
+  pub impact_speed_loss: f32,
+  pub impact_shake: Shake,
+  /// Shake started when a boost pad or the boost meter kicks in.
+  pub boost_shake: Shake,
+  #[schema(default = "25.0")]
+  pub shake_frequency: f32,
+}
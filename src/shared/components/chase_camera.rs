@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// Spring-arm settings for the camera that follows the local player's board.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct ChaseCameraComponent {
+  /// How far behind the board the camera sits when nothing is in the way.
+  #[schema(default = "5.0")]
+  pub arm_length: f32,
+  #[schema(default = "0.75")]
+  pub height: f32,
+  /// Height above the board the camera looks at.
+  #[schema(default = "0.05")]
+  pub look_height: f32,
+  /// How quickly the camera catches up with the arm's end. Higher is stiffer.
+  #[schema(default = "8.0")]
+  pub position_damping: f32,
+  /// How quickly the camera turns to face the board. Higher is stiffer.
+  #[schema(default = "10.0")]
+  pub rotation_damping: f32,
+  /// Gap kept between the camera and whatever the arm hit.
+  #[schema(default = "0.2")]
+  pub collision_margin: f32,
+
+  /// Radians the arm orbits per unit of mouse or right stick movement.
+  #[schema(default = "0.005")]
+  pub free_look_sensitivity: f32,
+  /// How far, in radians, the arm can be pitched up or down.
+  #[schema(default = "1.2")]
+  pub max_pitch: f32,
+  /// Seconds without free-look input before the arm swings back behind the
+  /// board.
+  #[schema(default = "1.5")]
+  pub recenter_delay: f32,
+  #[schema(default = "3.0")]
+  pub recenter_speed: f32,
+}
+
+impl Default for ChaseCameraComponent {
+  fn default() -> Self {
+    Self {
+      arm_length: 5.0,
+      height: 0.75,
+      look_height: 0.05,
+      position_damping: 8.0,
+      rotation_damping: 10.0,
+      collision_margin: 0.2,
+      free_look_sensitivity: 0.005,
+      max_pitch: 1.2,
+      recenter_delay: 1.5,
+      recenter_speed: 3.0,
+    }
+  }
+}
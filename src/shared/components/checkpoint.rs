@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// Marks a `Checkpoint` prefab. Racers have to pass checkpoints in `index`
+/// order; index 0 is the start/finish line, so passing it after the others
+/// completes a lap.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct CheckpointComponent {
+  #[schema(default = "0")]
+  pub index: u32,
+  /// How close to the prefab's translation a racer has to get to pass it.
+  #[schema(default = "8.0")]
+  pub radius: f32,
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// Seconds an entity spawned mid-race has left. The server and clients both
+/// count it down and despawn their own copy when it runs out, since clients
+/// aren't told about despawns.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct ExpiryComponent {
+  #[schema(default = "10.0")]
+  pub remaining: f32,
+}
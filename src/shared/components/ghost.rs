@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// Marks an entity replaying a recorded lap, both the avatar and its board.
+/// Ghosts have no physics, so they can be driven through.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema)]
+pub struct GhostComponent {
+  /// Whether this is the track's best lap rather than the owner's own.
+  #[schema(default = "false")]
+  pub track_best: bool,
+  /// Name of the `Ghost` prefab whose model clients draw this ghost with,
+  /// empty when the level has none for it.
+  pub look: String,
+}
@@ -0,0 +1,34 @@
+use engine::Entity;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// A rail boards can grind along, as a polyline through `points` in the
+/// rail's own space, so moving or turning its prefab moves the whole rail.
+/// Boards falling within `snap_distance` of it land on it, and have to keep
+/// their balance against the rail's `wobble` and `instability` or fall off.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema)]
+pub struct GrindRailComponent {
+  pub points: Vec<Vector3<f32>>,
+  #[schema(default = "1.5")]
+  pub snap_distance: f32,
+  /// How hard the rail sways a board's balance back and forth.
+  #[schema(default = "0.6")]
+  pub wobble: f32,
+  /// How fast an unbalanced board tips further over.
+  #[schema(default = "1.5")]
+  pub instability: f32,
+}
+
+/// Where a grinding board is on its rail.
+#[derive(Debug, Clone, Copy)]
+pub struct Grind {
+  pub rail: Entity,
+  /// Along the rail from its first point.
+  pub distance: f32,
+  /// 1 when riding towards the rail's last point, -1 otherwise.
+  pub direction: f32,
+  /// How far the board leans off the rail, falling off at -1 or 1.
+  pub balance: f32,
+  pub time: f32,
+}
@@ -0,0 +1,164 @@
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Schema)]
+pub enum Ability {
+  #[default]
+  None,
+  /// Leaves a cloud of smoke behind the board.
+  SmokeBomb,
+  /// Fills the boost meter.
+  Boost,
+  /// A few seconds of not being bumped.
+  Shield,
+}
+
+/// The item a racer holds, kept by the server. `Actions::UseItem` uses
+/// whatever it is; `uses` then goes up by one and `used` says what it was,
+/// so clients can play its effect.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct ItemComponent {
+  pub held: Ability,
+  #[schema(default = "0")]
+  pub uses: u32,
+  pub used: Ability,
+  /// Seconds of shield left.
+  #[schema(default = "0.0")]
+  pub shield: f32,
+}
+
+/// A box on the track that hands an item to racers driving through it with
+/// empty hands, and a bit of boost meter to everyone. It's gone for
+/// `respawn_time` seconds after each pickup, which the server shows through
+/// `available`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct ItemBoxComponent {
+  /// Half the size of the box along each of its axes.
+  pub half_extents: Vector3<f32>,
+  #[schema(default = "5.0")]
+  pub respawn_time: f32,
+  #[schema(default = "true")]
+  pub available: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Schema)]
+pub struct ItemWeight {
+  pub ability: Ability,
+  /// Weight for the race leader.
+  #[schema(default = "1.0")]
+  pub leader: f32,
+  /// Weight for the racer in last place. Places in between get a mix.
+  #[schema(default = "1.0")]
+  pub last: f32,
+}
+
+/// The items a level's item boxes hand out, and how likely each is depending
+/// on the place of the racer picking it up. Set on the level's `Track`
+/// prefab; levels without one use the default pool.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema)]
+pub struct ItemPoolComponent {
+  pub items: Vec<ItemWeight>,
+}
+
+impl Default for ItemPoolComponent {
+  fn default() -> Self {
+    Self {
+      items: vec![
+        ItemWeight {
+          ability: Ability::SmokeBomb,
+          leader: 3.0,
+          last: 1.0,
+        },
+        ItemWeight {
+          ability: Ability::Boost,
+          leader: 1.0,
+          last: 4.0,
+        },
+        ItemWeight {
+          ability: Ability::Shield,
+          leader: 2.0,
+          last: 1.0,
+        },
+      ],
+    }
+  }
+}
+
+impl ItemPoolComponent {
+  /// How likely each item is for the racer in `place` out of `racers`, as
+  /// unnormalised weights.
+  pub fn weights(&self, place: u32, racers: usize) -> Vec<(Ability, f32)> {
+    let behind = if racers > 1 {
+      (place.saturating_sub(1) as f32 / (racers - 1) as f32).min(1.0)
+    } else {
+      0.0
+    };
+
+    self
+      .items
+      .iter()
+      .map(|item| {
+        let weight = item.leader + (item.last - item.leader) * behind;
+        (item.ability, weight.max(0.0))
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn weight(weights: &[(Ability, f32)], ability: Ability) -> f32 {
+    weights
+      .iter()
+      .find(|(item, _)| *item == ability)
+      .map(|(_, weight)| *weight)
+      .unwrap()
+  }
+
+  #[test]
+  fn leader_and_last_get_the_pool_ends() {
+    let pool = ItemPoolComponent::default();
+
+    let leader = pool.weights(1, 5);
+    assert_eq!(weight(&leader, Ability::SmokeBomb), 3.0);
+    assert_eq!(weight(&leader, Ability::Boost), 1.0);
+    assert_eq!(weight(&leader, Ability::Shield), 2.0);
+
+    let last = pool.weights(5, 5);
+    assert_eq!(weight(&last, Ability::SmokeBomb), 1.0);
+    assert_eq!(weight(&last, Ability::Boost), 4.0);
+    assert_eq!(weight(&last, Ability::Shield), 1.0);
+  }
+
+  #[test]
+  fn places_in_between_blend_evenly() {
+    let middle = ItemPoolComponent::default().weights(3, 5);
+    assert_eq!(weight(&middle, Ability::SmokeBomb), 2.0);
+    assert_eq!(weight(&middle, Ability::Boost), 2.5);
+    assert_eq!(weight(&middle, Ability::Shield), 1.5);
+  }
+
+  #[test]
+  fn lone_racers_and_stale_places_stay_in_range() {
+    let pool = ItemPoolComponent::default();
+    assert_eq!(pool.weights(1, 1), pool.weights(1, 5));
+    assert_eq!(pool.weights(0, 5), pool.weights(1, 5));
+    assert_eq!(pool.weights(8, 5), pool.weights(5, 5));
+  }
+
+  #[test]
+  fn weights_never_go_negative() {
+    let pool = ItemPoolComponent {
+      items: vec![ItemWeight {
+        ability: Ability::Boost,
+        leader: -1.0,
+        last: 2.0,
+      }],
+    };
+    assert_eq!(pool.weights(1, 3), vec![(Ability::Boost, 0.0)]);
+    assert_eq!(pool.weights(3, 3), vec![(Ability::Boost, 2.0)]);
+  }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// Lap and split times of a racer, kept up to date by the server for the HUD.
+/// Times are in seconds; a zero best lap means there isn't one yet.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Registerable, Schema)]
+pub struct LapTimerComponent {
+  /// Laps completed.
+  #[schema(default = "0")]
+  pub lap: u32,
+  /// Index of the last checkpoint passed.
+  #[schema(default = "0")]
+  pub checkpoint: u32,
+  /// Lap time when that checkpoint was passed.
+  #[schema(default = "0.0")]
+  pub split: f32,
+  /// Split compared to the personal best lap at the same checkpoint. Negative
+  /// is ahead.
+  #[schema(default = "0.0")]
+  pub split_delta: f32,
+  #[schema(default = "false")]
+  pub has_split_delta: bool,
+  #[schema(default = "0.0")]
+  pub last_lap: f32,
+  #[schema(default = "0.0")]
+  pub best_lap: f32,
+}
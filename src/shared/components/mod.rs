@@ -1,7 +1,87 @@
+pub mod animation_controller;
+pub mod board;
+pub mod boost_meter;
+pub mod boost_pad;
+pub mod bump;
+pub mod camera_effects;
+pub mod chase_camera;
+pub mod checkpoint;
+pub mod expiry;
+pub mod ghost;
+pub mod grind_rail;
+pub mod item;
+pub mod lap_timer;
+pub mod racer;
+pub mod recovery;
+pub mod respawn;
+pub mod role;
+pub mod spectator_camera;
+pub mod track_spline;
+pub mod trick;
+pub mod wall_contact;
+
+use engine::application::scene::component_registry::Access;
 use nalgebra::Vector3;
 use serde::{Deserialize, Serialize};
 use tagged::{Registerable, Schema};
 
+pub use animation_controller::{AnimationControllerComponent, StateAnimation};
+pub use board::BoardComponent;
+pub use boost_meter::BoostMeterComponent;
+pub use boost_pad::{BoostPadComponent, SpeedZoneComponent};
+pub use bump::BumpComponent;
+pub use camera_effects::{CameraEffectsComponent, Easing, Shake, StateShake};
+pub use chase_camera::ChaseCameraComponent;
+pub use checkpoint::CheckpointComponent;
+pub use expiry::ExpiryComponent;
+pub use ghost::GhostComponent;
+pub use grind_rail::{Grind, GrindRailComponent};
+pub use item::{Ability, ItemBoxComponent, ItemComponent, ItemPoolComponent, ItemWeight};
+pub use lap_timer::LapTimerComponent;
+pub use racer::RacerComponent;
+pub use recovery::{RecoveryComponent, TrackBoundsComponent};
+pub use respawn::{RespawnComponent, RespawnReason};
+pub use role::{Role, RoleComponent};
+pub use spectator_camera::SpectatorCameraComponent;
+pub use track_spline::{TrackSplineComponent, TrackSplinePoint};
+pub use trick::TrickComponent;
+pub use wall_contact::{WallContactComponent, WallContactKind};
+
+/// What the board is doing right now, as worked out by the
+/// `PlayerMovementSystem` every frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Schema)]
+pub enum BoardState {
+  #[default]
+  Idle,
+  Cruising,
+  MaxSpeed,
+  LeaningLeft,
+  LeaningRight,
+  Braking,
+  Drifting,
+  Airborne,
+  Grabbing,
+  Landing,
+  Grinding,
+}
+
+impl BoardState {
+  /// The closest state to fall back on when an avatar has nothing set up for
+  /// this one. `Idle` is the end of the chain.
+  pub fn fallback(&self) -> Option<Self> {
+    match self {
+      Self::Idle => None,
+      Self::Cruising => Some(Self::Idle),
+      Self::MaxSpeed | Self::LeaningLeft | Self::LeaningRight | Self::Braking => {
+        Some(Self::Cruising)
+      }
+      Self::Drifting => Some(Self::Braking),
+      Self::Grabbing => Some(Self::Airborne),
+      Self::Airborne | Self::Landing | Self::Grinding => Some(Self::Cruising),
+    }
+  }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
 pub struct PlayerMovementComponent {
   #[serde(skip, default = "default_down_vector")]
@@ -21,10 +101,143 @@ pub struct PlayerMovementComponent {
   pub height_from_surface_speed: f32,
   #[schema(default = "150.0")]
   pub deceleration: f32,
+  /// Upward speed of an uncharged hop.
+  #[schema(default = "4.0")]
+  pub jump_velocity: f32,
+  /// Extra upward speed of a fully charged hop.
+  #[schema(default = "4.0")]
+  pub jump_charge_velocity: f32,
+  /// Seconds of holding `Actions::Jump` to fully charge a hop.
+  #[schema(default = "0.6")]
+  pub jump_charge_time: f32,
+  /// Pull towards the last surface the board hovered over, once nothing is
+  /// under it.
+  #[schema(default = "20.0")]
+  pub gravity: f32,
+  /// How much of `rotation_speed` is left for steering in the air.
+  #[schema(default = "0.4")]
+  pub air_steering: f32,
+  /// How fast the nose tips up or down in the air.
+  #[schema(default = "60.0")]
+  pub pitch_speed: f32,
+  /// Spinning speed in the air while `Actions::Trick` is held.
+  #[schema(default = "400.0")]
+  pub trick_spin_speed: f32,
+  /// Flipping speed in the air while `Actions::Trick` is held.
+  #[schema(default = "300.0")]
+  pub trick_flip_speed: f32,
+  /// How square to the surface, out of 1, a landing has to be to keep all
+  /// its speed.
+  #[schema(default = "0.8")]
+  pub landing_tolerance: f32,
+  /// Share of speed lost by the worst possible landing.
+  #[schema(default = "0.5")]
+  pub landing_speed_loss: f32,
+
+  #[serde(skip)]
+  pub current_velocity: f32,
+  #[serde(skip)]
+  pub grounded: bool,
+  #[serde(skip)]
+  pub state: BoardState,
+  #[serde(skip)]
+  pub state_time: f32,
+  /// Speed away from the surface, while jumping or falling.
+  #[serde(skip)]
+  pub air_velocity: f32,
+  /// Seconds `Actions::Jump` has been held for.
+  #[serde(skip)]
+  pub jump_charge: f32,
+  /// How square to the surface the last landing was, out of 1.
+  #[serde(skip)]
+  pub last_landing: f32,
+  /// The rail the board is grinding, if any.
+  #[serde(skip)]
+  pub grind: Option<Grind>,
+  /// Seconds until the board can land on a rail again after leaving one.
+  #[serde(skip)]
+  pub grind_cooldown: f32,
+  /// Push from the last bump, fading out on top of the board's own speed.
+  #[serde(skip)]
+  pub bump_velocity: Vector3<f32>,
+  /// Seconds left without control after a bump.
+  #[serde(skip)]
+  pub stunned: f32,
+  /// The last `BumpComponent::bumps` the board reacted to.
+  #[serde(skip)]
+  pub bumps_seen: Option<u32>,
+  /// The last `WallContactComponent::contacts` the board reacted to.
+  #[serde(skip)]
+  pub wall_contacts_seen: Option<u32>,
+  /// Top speed multiplier from the last boost pad, easing back to 1.
+  #[serde(skip, default = "default_multiplier")]
+  pub boost_multiplier: f32,
+  /// Seconds left at full boost.
+  #[serde(skip)]
+  pub boost_time: f32,
+  /// Top speed multiplier from the speed zone the board is in, if any.
+  #[serde(skip, default = "default_multiplier")]
+  pub zone_multiplier: f32,
+  /// Top speed multiplier from spending the boost meter.
+  #[serde(skip, default = "default_multiplier")]
+  pub meter_speed_multiplier: f32,
+  /// Acceleration multiplier from spending the boost meter.
+  #[serde(skip, default = "default_multiplier")]
+  pub meter_acceleration_multiplier: f32,
 }
 
 fn default_down_vector() -> Vector3<f32> {
   Vector3::new(0.0, -1.0, 0.0)
 }
 
-impl PlayerMovementComponent {}
+fn default_multiplier() -> f32 {
+  1.0
+}
+
+impl PlayerMovementComponent {
+  /// Top speed right now, with boosts and speed zones applied.
+  pub fn effective_max_velocity(&self) -> f32 {
+    self.max_velocity * self.boost_multiplier * self.zone_multiplier * self.meter_speed_multiplier
+  }
+
+  /// Acceleration right now, with the boost meter applied.
+  pub fn effective_acceleration(&self) -> f32 {
+    self.acceleration * self.meter_acceleration_multiplier
+  }
+
+  pub fn set_state(&mut self, state: BoardState) {
+    if self.state != state {
+      self.state = state;
+      self.state_time = 0.0;
+    }
+  }
+}
+
+pub fn register_components() {
+  PlayerMovementComponent::register();
+  RoleComponent::register();
+  BoardComponent::register();
+  AnimationControllerComponent::register();
+  ChaseCameraComponent::register();
+  CameraEffectsComponent::register();
+  RacerComponent::register();
+  SpectatorCameraComponent::register();
+  CheckpointComponent::register();
+  GhostComponent::register();
+  LapTimerComponent::register();
+  TrackSplineComponent::register();
+  RecoveryComponent::register();
+  TrackBoundsComponent::register();
+  RespawnComponent::register();
+  BoostPadComponent::register();
+  SpeedZoneComponent::register();
+  BoostMeterComponent::register();
+  TrickComponent::register();
+  GrindRailComponent::register();
+  BumpComponent::register();
+  WallContactComponent::register();
+  ItemComponent::register();
+  ItemBoxComponent::register();
+  ItemPoolComponent::register();
+  ExpiryComponent::register();
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// Where a player stands in the current race. The server keeps it up to date
+/// and spectators use it to find the leader.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct RacerComponent {
+  /// 1 for the leader.
+  #[schema(default = "1")]
+  pub place: u32,
+  #[schema(default = "false")]
+  pub finished: bool,
+}
@@ -0,0 +1,53 @@
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// When the server gives up on a racer and puts them back at their last
+/// checkpoint. Put it on any world object in the level; the defaults are used
+/// when the level has none.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct RecoveryComponent {
+  /// Racers below this height are respawned straight away.
+  #[schema(default = "-10.0")]
+  pub kill_plane: f32,
+  /// Seconds a racer can drive against the track before being turned around.
+  #[schema(default = "3.0")]
+  pub wrong_way_time: f32,
+  /// Seconds a racer can try to move without getting anywhere.
+  #[schema(default = "4.0")]
+  pub stuck_time: f32,
+  /// Below this many units per second a racer isn't getting anywhere.
+  #[schema(default = "0.5")]
+  pub stuck_speed: f32,
+  /// Seconds a respawned racer is left alone by other boards and abilities.
+  #[schema(default = "2.0")]
+  pub invulnerable_time: f32,
+  /// Seconds between a player asking for a reset and getting it.
+  #[schema(default = "1.0")]
+  pub reset_delay: f32,
+  /// Seconds after a reset before the player can ask for another.
+  #[schema(default = "5.0")]
+  pub reset_cooldown: f32,
+}
+
+impl Default for RecoveryComponent {
+  fn default() -> Self {
+    Self {
+      kill_plane: -10.0,
+      wrong_way_time: 3.0,
+      stuck_time: 4.0,
+      stuck_speed: 0.5,
+      invulnerable_time: 2.0,
+      reset_delay: 1.0,
+      reset_cooldown: 5.0,
+    }
+  }
+}
+
+/// Marks a `TrackBounds` prefab: a box, centred on the prefab and turned with
+/// it, that racers are allowed to be in. Levels without any don't check.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct TrackBoundsComponent {
+  /// Half the size of the box along each of its axes.
+  pub half_extents: Vector3<f32>,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// Why a racer was last put back on the track.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Schema)]
+pub enum RespawnReason {
+  #[default]
+  None,
+  /// Fell below the level's kill plane.
+  KillPlane,
+  /// Left every `TrackBoundsComponent` volume.
+  OutOfBounds,
+  /// Drove against the track for too long.
+  WrongWay,
+  /// Tried to move but went nowhere for too long.
+  Stuck,
+  /// Asked for it with `Actions::Reset`.
+  Manual,
+}
+
+/// Lets a racer's client know the server respawned them. `respawns` goes up
+/// by one on every respawn, so the client can tell a new one apart from the
+/// last one, `invulnerable` counts down the seconds the racer is left alone
+/// by other boards and abilities, and `reset_in` counts down to a reset the
+/// player asked for. `resets` counts the resets they asked for this race.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct RespawnComponent {
+  #[schema(default = "0")]
+  pub respawns: u32,
+  pub reason: RespawnReason,
+  #[schema(default = "0.0")]
+  pub invulnerable: f32,
+  #[schema(default = "0.0")]
+  pub reset_in: f32,
+  #[schema(default = "0")]
+  pub resets: u32,
+}
+
+impl RespawnComponent {
+  pub fn is_invulnerable(&self) -> bool {
+    self.invulnerable > 0.0
+  }
+}
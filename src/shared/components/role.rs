@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Schema)]
+pub enum Role {
+  PlayerAvatar,
+  Board,
+  Character,
+  AbilityEffect,
+  WorldObject,
+  SpawnPoint,
+  Checkpoint,
+  TrackSpline,
+  TrackBounds,
+  /// A translucent look for ghosts of the board or character of the same
+  /// name, minus the " Ghost" suffix.
+  Ghost,
+  #[serde(other)]
+  Unknown,
+}
+
+/// Tells the server what a level prefab is for. Prefabs are routed by role
+/// rather than by name, so new boards or abilities only need a role in the
+/// level file.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct RoleComponent {
+  pub role: Role,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// Settings for the camera used by clients that aren't racing. Put it on any
+/// world object in the level; the defaults are used when the level has none.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct SpectatorCameraComponent {
+  /// How far behind the followed racer the camera sits.
+  #[schema(default = "7.0")]
+  pub follow_distance: f32,
+  #[schema(default = "2.0")]
+  pub follow_height: f32,
+  /// How quickly the camera catches up with the followed racer. Higher is
+  /// stiffer.
+  #[schema(default = "4.0")]
+  pub follow_damping: f32,
+  /// Units per second when free-flying.
+  #[schema(default = "20.0")]
+  pub fly_speed: f32,
+  /// Radians the camera turns per unit of mouse or right stick movement when
+  /// free-flying.
+  #[schema(default = "0.005")]
+  pub look_sensitivity: f32,
+}
+
+impl Default for SpectatorCameraComponent {
+  fn default() -> Self {
+    Self {
+      follow_distance: 7.0,
+      follow_height: 2.0,
+      follow_damping: 4.0,
+      fly_speed: 20.0,
+      look_sensitivity: 0.005,
+    }
+  }
+}
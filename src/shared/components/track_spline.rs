@@ -0,0 +1,26 @@
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Schema)]
+pub struct TrackSplinePoint {
+  /// World space, on the track surface.
+  pub position: Vector3<f32>,
+  /// Drivable width of the track here, edge to edge.
+  #[schema(default = "12.0")]
+  pub width: f32,
+  /// How far the track leans into the corner here, in radians. Positive
+  /// raises the left edge.
+  #[schema(default = "0.0")]
+  pub banking: f32,
+}
+
+/// The racing line of a level, as control points in driving order. The curve
+/// passes through every point, and a closed track joins the last one back to
+/// the first, which should sit on the start/finish line.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema)]
+pub struct TrackSplineComponent {
+  pub points: Vec<TrackSplinePoint>,
+  #[schema(default = "true")]
+  pub closed: bool,
+}
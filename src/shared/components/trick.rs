@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+/// A racer's trick score, kept by the server for the HUD and results. Tricks
+/// done in one jump only count once the board lands cleanly, multiplied by
+/// `combo`, the number of trick landings in a row. `landed` and `bails` go
+/// up by one on every trick landing and every bail, so the client can tell a
+/// new one apart from the last one.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema)]
+pub struct TrickComponent {
+  #[schema(default = "0")]
+  pub score: u32,
+  #[schema(default = "0")]
+  pub combo: u32,
+  /// Points the tricks in the current jump are worth if it lands cleanly.
+  #[schema(default = "0")]
+  pub pending: u32,
+  #[schema(default = "0")]
+  pub landed: u32,
+  #[schema(default = "0")]
+  pub bails: u32,
+  /// Names of the tricks in the last trick landing, like "Backflip + 360".
+  pub last_tricks: String,
+  #[schema(default = "0")]
+  pub last_points: u32,
+}
@@ -0,0 +1,44 @@
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use tagged::{Registerable, Schema};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Schema)]
+pub enum WallContactKind {
+  #[default]
+  None,
+  /// Running along a wall.
+  Scrape,
+  /// Driving into one.
+  Impact,
+}
+
+/// A racer's board touching the track's walls. The server fills in the last
+/// contact, and `contacts` goes up by one on every one so the board's
+/// movement can take `speed_loss` off its speed once, and clients can put
+/// sparks at `point`. Impacts cost more speed the straighter the board hit
+/// the wall, up to `max_impact_loss` head-on; scrapes cost `scrape_loss` of
+/// it per second.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema)]
+pub struct WallContactComponent {
+  #[schema(default = "0")]
+  pub contacts: u32,
+  pub kind: WallContactKind,
+  pub point: Vector3<f32>,
+  /// Pointing out of the wall.
+  pub normal: Vector3<f32>,
+  /// Share of the board's speed the contact costs.
+  #[schema(default = "0.0")]
+  pub speed_loss: f32,
+
+  /// Hits at least this many radians off running along the wall are
+  /// impacts, shallower ones scrapes.
+  #[schema(default = "0.6")]
+  pub impact_angle: f32,
+  #[schema(default = "0.7")]
+  pub max_impact_loss: f32,
+  #[schema(default = "0.3")]
+  pub scrape_loss: f32,
+  /// Boards slower than this just touch walls.
+  #[schema(default = "2.0")]
+  pub min_speed: f32,
+}
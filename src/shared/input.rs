@@ -1,19 +1,30 @@
-#![cfg(target_arch = "wasm32")]
-
 use engine::application::devices::{
   Devices, KeyboardKey, MouseButton, MouseEvent, MouseState, WindowEvent,
 };
 use engine::systems::input::Input;
 use nalgebra::{Vector2, Vector3};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+use crate::shared::loadout::{self, Loadout};
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Actions {
   Brake,
-  SmokeBomb,
+  UseItem,
+  LookBehind,
+  SpectateNext,
+  SpectatePrevious,
+  SpectateMode,
+  Restart,
+  Reset,
+  Boost,
+  Jump,
+  Trick,
+  Grab,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PlayerInput {
   pub direction_vector: Vector3<f32>,
   pub mouse_delta: Vector2<f32>,
@@ -25,8 +36,13 @@ pub struct PlayerInput {
   pub right_click: bool,
   pub canvas: (u32, u32),
   pub pixel_ratio: f32,
+  #[serde(skip)]
   pub keyboard: Vec<KeyboardKey>,
   pub actions: HashSet<Actions>,
+  /// The loadout picked on the join screen, sent once after connecting.
+  pub join: Option<Loadout>,
+  /// Whether the player wants to watch rather than race right now.
+  pub spectate: bool,
 }
 
 impl Default for PlayerInput {
@@ -41,10 +57,15 @@ impl Input for PlayerInput {
     self.mouse_delta = Vector2::zeros();
     self.mouse_position = Vector2::zeros();
     self.actions.clear();
+    self.join = None;
   }
 
   fn from_devices(&mut self, device: &mut Devices) {
     self.focused = device.window.focus;
+    if let Some(join) = loadout::take_join() {
+      self.join = Some(join);
+    }
+    self.spectate = loadout::spectating();
 
     self.canvas = device.window.canvas_size;
     self.pixel_ratio = device.window.pixel_ratio;
@@ -84,11 +105,41 @@ impl Input for PlayerInput {
         KeyboardKey::W | KeyboardKey::Up => self.direction_vector.z = 1.0,
         KeyboardKey::S | KeyboardKey::Down => self.direction_vector.z = -1.0,
         KeyboardKey::E => {
-          self.actions.insert(Actions::SmokeBomb);
+          self.actions.insert(Actions::UseItem);
+        }
+        KeyboardKey::Q => {
+          self.actions.insert(Actions::LookBehind);
         }
         KeyboardKey::Space => {
           self.actions.insert(Actions::Brake);
         }
+        KeyboardKey::X => {
+          self.actions.insert(Actions::SpectateNext);
+        }
+        KeyboardKey::Z => {
+          self.actions.insert(Actions::SpectatePrevious);
+        }
+        KeyboardKey::C => {
+          self.actions.insert(Actions::SpectateMode);
+        }
+        KeyboardKey::R => {
+          self.actions.insert(Actions::Restart);
+        }
+        KeyboardKey::T => {
+          self.actions.insert(Actions::Reset);
+        }
+        KeyboardKey::F => {
+          self.actions.insert(Actions::Boost);
+        }
+        KeyboardKey::V => {
+          self.actions.insert(Actions::Jump);
+        }
+        KeyboardKey::G => {
+          self.actions.insert(Actions::Trick);
+        }
+        KeyboardKey::H => {
+          self.actions.insert(Actions::Grab);
+        }
         _ => {}
       }
     }
@@ -105,10 +156,10 @@ impl Input for PlayerInput {
       if gamepad.left_joystick.y > MAX_EPSILON || gamepad.left_joystick.y < MIN_EPSILON {
         self.direction_vector.z = -gamepad.left_joystick.y;
       }
-      if gamepad.left_joystick.x > MAX_EPSILON || gamepad.left_joystick.x < MIN_EPSILON {
+      if gamepad.right_joystick.x > MAX_EPSILON || gamepad.right_joystick.x < MIN_EPSILON {
         self.mouse_delta.x = gamepad.right_joystick.x;
       }
-      if gamepad.left_joystick.y > MAX_EPSILON || gamepad.left_joystick.y < MIN_EPSILON {
+      if gamepad.right_joystick.y > MAX_EPSILON || gamepad.right_joystick.y < MIN_EPSILON {
         self.mouse_delta.y = gamepad.right_joystick.y;
       }
     }
@@ -144,6 +195,8 @@ impl PlayerInput {
       pixel_ratio: 1.0,
       keyboard: Vec::new(),
       actions: HashSet::new(),
+      join: None,
+      spectate: false,
     }
   }
 }
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// What the player picked on the join screen. The client sends it once, as the
+/// join message in its first `PlayerInput`, and the server validates it
+/// against the level before spawning the player with it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Loadout {
+  pub board: Option<String>,
+  pub avatar: Option<String>,
+  /// Watch the race instead of joining it.
+  pub spectate: bool,
+}
+
+static JOIN: Mutex<Option<Loadout>> = Mutex::new(None);
+
+/// Queues the join message for the next input sent to the server.
+pub fn request_join(loadout: Loadout) {
+  *JOIN.lock().unwrap() = Some(loadout);
+}
+
+/// Hands the queued join message out, once.
+pub fn take_join() -> Option<Loadout> {
+  JOIN.lock().unwrap().take()
+}
+
+static SPECTATING: AtomicBool = AtomicBool::new(false);
+
+/// Switches between racing and watching mid-session. Sent with every input,
+/// so the server moves the player over as soon as it sees the change.
+pub fn set_spectating(spectating: bool) {
+  SPECTATING.store(spectating, Ordering::Relaxed);
+}
+
+pub fn spectating() -> bool {
+  SPECTATING.load(Ordering::Relaxed)
+}
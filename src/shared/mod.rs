@@ -1,3 +1,9 @@
 pub mod components;
 pub mod input;
+pub mod loadout;
+pub mod movement;
+pub mod prefabs;
+pub mod rail;
+pub mod replay;
 pub mod systems;
+pub mod volume;
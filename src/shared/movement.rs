@@ -0,0 +1,550 @@
+//! Per-board movement, shared by the client's `PlayerMovementSystem` and the
+//! server's bots so both drive boards exactly the same way.
+
+use engine::application::{
+  components::PhysicsComponent,
+  scene::{Scene, TransformComponent},
+};
+use engine::systems::physics::PhysicsController;
+use nalgebra::{Rotation3, Vector3};
+use rapier3d::prelude::*;
+
+use crate::shared::components::{
+  BoardState, BoostMeterComponent, BoostPadComponent, BumpComponent, Grind,
+  PlayerMovementComponent, SpeedZoneComponent, WallContactComponent,
+};
+use crate::shared::input::{Actions, PlayerInput};
+use crate::shared::rail::GrindRails;
+use crate::shared::volume::BoxVolume;
+
+const LANDING_TIME: f32 = 0.3;
+const MIN_MOVING_SPEED: f32 = 1.0;
+const MAX_SPEED_RATIO: f32 = 0.95;
+/// How much a spent boost multiplier drops per second on its way back to 1.
+const BOOST_FALLOFF: f32 = 0.5;
+/// How fast full steering brings a grinding board's balance back.
+const BALANCE_CONTROL: f32 = 2.5;
+/// How often a rail sways a grinding board back and forth, in radians per
+/// second.
+const RAIL_WOBBLE_RATE: f32 = 3.0;
+/// How much of the gap between where a grinding board faces and where the
+/// rail goes is closed per frame.
+const RAIL_ALIGNMENT: f32 = 0.2;
+/// Share of a bump's push lost per second.
+const BUMP_DAMPING: f32 = 3.0;
+/// Seconds after leaving a rail before the board can land on one again, so
+/// it doesn't snap straight back onto the one it left.
+const GRIND_COOLDOWN: f32 = 0.5;
+
+/// The level's boost pads and speed zones, gathered once per frame.
+#[derive(Debug, Default)]
+pub struct SpeedVolumes {
+  pads: Vec<(BoxVolume, BoostPadComponent)>,
+  zones: Vec<(BoxVolume, f32)>,
+}
+
+impl SpeedVolumes {
+  pub fn gather(scene: &mut Scene) -> Self {
+    let pads = scene
+      .query_mut::<(&BoostPadComponent, &TransformComponent)>()
+      .into_iter()
+      .map(|(_, (pad, transform))| (BoxVolume::new(transform, pad.half_extents), *pad))
+      .collect();
+    let zones = scene
+      .query_mut::<(&SpeedZoneComponent, &TransformComponent)>()
+      .into_iter()
+      .map(|(_, (zone, transform))| {
+        (
+          BoxVolume::new(transform, zone.half_extents),
+          zone.multiplier,
+        )
+      })
+      .collect();
+
+    Self { pads, zones }
+  }
+}
+
+/// Boosts the board if it's driving over a pad the right way, and caps its
+/// top speed to the speed zone it's in.
+pub fn apply_speed_volumes(
+  volumes: &SpeedVolumes,
+  player_movement: &mut PlayerMovementComponent,
+  transform: &TransformComponent,
+  delta_time: f32,
+) {
+  let position = transform.translation;
+  let forward = transform.get_euler_direction().into_inner();
+
+  player_movement.boost_time = (player_movement.boost_time - delta_time).max(0.0);
+  if player_movement.boost_time <= 0.0 {
+    player_movement.boost_multiplier =
+      (player_movement.boost_multiplier - BOOST_FALLOFF * delta_time).max(1.0);
+  }
+
+  for (volume, pad) in &volumes.pads {
+    if !volume.contains(&position) || player_movement.current_velocity <= 0.0 {
+      continue;
+    }
+    if volume.world_direction(&pad.direction).dot(&forward) <= 0.0 {
+      continue;
+    }
+
+    player_movement.boost_multiplier = player_movement.boost_multiplier.max(pad.strength);
+    player_movement.boost_time = player_movement.boost_time.max(pad.duration);
+    // the kick: straight up to the board's usual top speed
+    player_movement.current_velocity = player_movement
+      .current_velocity
+      .max(player_movement.max_velocity);
+  }
+
+  player_movement.zone_multiplier = volumes
+    .zones
+    .iter()
+    .filter(|(volume, _)| volume.contains(&position))
+    .map(|(_, multiplier)| *multiplier)
+    .reduce(f32::min)
+    .unwrap_or(1.0);
+}
+
+/// Speeds the board up while its boost meter is being spent.
+pub fn apply_boost_meter(
+  meter: Option<&BoostMeterComponent>,
+  player_movement: &mut PlayerMovementComponent,
+) {
+  let (speed, acceleration) = match meter {
+    Some(meter) if meter.active => (meter.speed_multiplier, meter.acceleration_multiplier),
+    _ => (1.0, 1.0),
+  };
+  player_movement.meter_speed_multiplier = speed;
+  player_movement.meter_acceleration_multiplier = acceleration;
+}
+
+/// Picks up a new bump from the server, and fades out the last one.
+pub fn apply_bump(
+  bump: Option<&BumpComponent>,
+  player_movement: &mut PlayerMovementComponent,
+  delta_time: f32,
+) {
+  player_movement.stunned = (player_movement.stunned - delta_time).max(0.0);
+  player_movement.bump_velocity *= (1.0 - BUMP_DAMPING * delta_time).max(0.0);
+
+  let Some(bump) = bump else {
+    return;
+  };
+  // the first one seen is an old bump, from before this board was around
+  if player_movement
+    .bumps_seen
+    .is_some_and(|seen| seen != bump.bumps)
+  {
+    player_movement.bump_velocity += bump.impulse;
+    player_movement.stunned = player_movement.stunned.max(bump.stun);
+  }
+  player_movement.bumps_seen = Some(bump.bumps);
+}
+
+/// Takes the speed a new wall contact from the server costs off the board.
+pub fn apply_wall_contact(
+  wall: Option<&WallContactComponent>,
+  player_movement: &mut PlayerMovementComponent,
+) {
+  let Some(wall) = wall else {
+    return;
+  };
+  if player_movement
+    .wall_contacts_seen
+    .is_some_and(|seen| seen != wall.contacts)
+  {
+    player_movement.current_velocity *= 1.0 - wall.speed_loss;
+  }
+  player_movement.wall_contacts_seen = Some(wall.contacts);
+}
+
+pub fn accelerate(
+  forward_input: f32,
+  player_movement: &mut PlayerMovementComponent,
+  delta_time: f32,
+) {
+  if forward_input != 0.0 {
+    let max_velocity = player_movement.effective_max_velocity();
+    let speed = player_movement.current_velocity.abs();
+
+    // over the cap once a boost wears off or in a slow zone, so ease down to it
+    if speed > max_velocity {
+      let slowed = (speed - player_movement.deceleration * delta_time).max(max_velocity);
+      player_movement.current_velocity = slowed.copysign(player_movement.current_velocity);
+      return;
+    }
+
+    player_movement.current_velocity +=
+      player_movement.effective_acceleration() * delta_time * forward_input;
+    player_movement.current_velocity = player_movement
+      .current_velocity
+      .clamp(-max_velocity, max_velocity);
+  } else {
+    decelerate(player_movement, delta_time);
+  }
+}
+
+pub fn decelerate(player_movement: &mut PlayerMovementComponent, delta_time: f32) {
+  if player_movement.current_velocity > 0.0 {
+    player_movement.current_velocity -= player_movement.deceleration * delta_time;
+    if player_movement.current_velocity < 0.0 {
+      player_movement.current_velocity = 0.0;
+    }
+  } else if player_movement.current_velocity < 0.0 {
+    player_movement.current_velocity += player_movement.deceleration * delta_time;
+    if player_movement.current_velocity > 0.0 {
+      player_movement.current_velocity = 0.0;
+    }
+  }
+}
+
+/// Lands a falling board on a rail close enough to it, and carries grinding
+/// boards along theirs at the speed they landed with. Steering keeps the
+/// board balanced, losing balance throws it off the rail with a speed loss,
+/// and jumping or running off the end leaves the rail. Returns whether the
+/// board is grinding, which `drive` and `hover` leave alone.
+pub fn grind(
+  physics_controller: &PhysicsController,
+  rails: &GrindRails,
+  input: &PlayerInput,
+  player_component: &mut PlayerMovementComponent,
+  physics: &mut PhysicsComponent,
+  transform: &TransformComponent,
+  delta_time: f32,
+) -> bool {
+  let position = transform.translation;
+  let forward = transform.get_euler_direction().into_inner();
+  player_component.grind_cooldown = (player_component.grind_cooldown - delta_time).max(0.0);
+
+  let mut grind = match player_component.grind {
+    Some(grind) => grind,
+    None => {
+      let falling = !player_component.grounded && player_component.air_velocity <= 0.0;
+      if !falling || player_component.grind_cooldown > 0.0 {
+        return false;
+      }
+      let Some((rail, at)) = rails.nearest(&position) else {
+        return false;
+      };
+
+      player_component.air_velocity = 0.0;
+      player_component.jump_charge = 0.0;
+      Grind {
+        rail: rail.entity,
+        distance: at.distance,
+        direction: if forward.dot(&at.tangent) >= 0.0 {
+          1.0
+        } else {
+          -1.0
+        },
+        balance: 0.0,
+        time: 0.0,
+      }
+    }
+  };
+
+  let Some(rail) = rails.get(grind.rail) else {
+    leave_rail(player_component);
+    return false;
+  };
+
+  if input.actions.contains(&Actions::Jump) {
+    leave_rail(player_component);
+    player_component.air_velocity = player_component.jump_velocity;
+    return false;
+  }
+
+  grind.time += delta_time;
+  grind.balance += (rail.wobble * (grind.time * RAIL_WOBBLE_RATE).sin()
+    + grind.balance * rail.instability
+    + input.direction_vector.x * BALANCE_CONTROL)
+    * delta_time;
+  if grind.balance.abs() >= 1.0 {
+    leave_rail(player_component);
+    player_component.current_velocity *= 1.0 - player_component.landing_speed_loss;
+    return false;
+  }
+
+  // the same speed `drive` moves the board at
+  let speed = player_component.current_velocity.abs() * delta_time;
+  grind.distance += grind.direction * speed * delta_time;
+  if grind.distance < 0.0 || grind.distance > rail.length() {
+    leave_rail(player_component);
+    return false;
+  }
+
+  let (point, tangent) = rail.at(grind.distance);
+  let target = point - player_component.down_vector * player_component.max_height_from_surface;
+  physics_controller.set_linvel(physics, (target - position) / delta_time.max(f32::EPSILON));
+  physics_controller.set_angvel(
+    physics,
+    forward.cross(&(tangent * grind.direction)) * RAIL_ALIGNMENT / delta_time.max(f32::EPSILON),
+  );
+
+  player_component.grind = Some(grind);
+  true
+}
+
+fn leave_rail(player_component: &mut PlayerMovementComponent) {
+  player_component.grind = None;
+  player_component.grind_cooldown = GRIND_COOLDOWN;
+  player_component.grounded = false;
+}
+
+/// Charges a hop while `Actions::Jump` is held on the ground, and lets it go
+/// on release, higher the longer it was held.
+pub fn jump(input: &PlayerInput, player_component: &mut PlayerMovementComponent, delta_time: f32) {
+  if !player_component.grounded {
+    player_component.jump_charge = 0.0;
+    return;
+  }
+
+  if input.actions.contains(&Actions::Jump) {
+    player_component.jump_charge =
+      (player_component.jump_charge + delta_time).min(player_component.jump_charge_time);
+    return;
+  }
+
+  if player_component.jump_charge > 0.0 {
+    let charge = player_component.jump_charge / player_component.jump_charge_time.max(f32::EPSILON);
+    player_component.air_velocity =
+      player_component.jump_velocity + player_component.jump_charge_velocity * charge;
+    player_component.jump_charge = 0.0;
+    player_component.grounded = false;
+  }
+}
+
+/// Applies throttle, brake and steering from `input` to the board. In the
+/// air the board keeps its speed, steers less, and throttle tips its nose
+/// down or up instead, a lot faster while `Actions::Trick` is held. A bumped
+/// board is pushed along by the bump, and coasts without control while it's
+/// stunned.
+pub fn drive(
+  physics_controller: &PhysicsController,
+  input: &PlayerInput,
+  player_component: &mut PlayerMovementComponent,
+  physics: &mut PhysicsComponent,
+  transform: &TransformComponent,
+  delta_time: f32,
+) {
+  let forward_input = input.direction_vector.z;
+  let right_input = -input.direction_vector.x;
+
+  let transform_direction = transform.get_euler_direction();
+
+  if player_component.grind.is_some() {
+    return;
+  }
+
+  jump(input, player_component, delta_time);
+
+  if !player_component.grounded {
+    let forward = transform_direction.into_inner();
+    let player_up = -player_component.down_vector;
+    let right = forward.cross(&player_up);
+    let (spin_speed, flip_speed) = if input.actions.contains(&Actions::Trick) {
+      (
+        player_component.trick_spin_speed,
+        player_component.trick_flip_speed,
+      )
+    } else {
+      (
+        player_component.rotation_speed * player_component.air_steering,
+        player_component.pitch_speed,
+      )
+    };
+
+    physics_controller.set_linvel(
+      physics,
+      forward * player_component.current_velocity * delta_time + player_component.bump_velocity,
+    );
+    physics_controller.set_angvel(
+      physics,
+      player_up * spin_speed * delta_time * right_input
+        - right * flip_speed * delta_time * forward_input,
+    );
+    return;
+  }
+
+  if player_component.stunned > 0.0 {
+    decelerate(player_component, delta_time);
+    physics_controller.set_linvel(
+      physics,
+      transform_direction.into_inner() * player_component.current_velocity * delta_time
+        + player_component.bump_velocity,
+    );
+    physics_controller.set_angvel(physics, Vector3::zeros());
+    return;
+  }
+
+  accelerate(forward_input, player_component, delta_time);
+  physics_controller.set_linvel(
+    physics,
+    transform_direction.into_inner()
+      * player_component.current_velocity
+      * delta_time
+      * forward_input
+      + player_component.bump_velocity,
+  );
+
+  // TODO: this needs to take into account the player's entire rotation, not just y
+  let player_up = -player_component.down_vector;
+  physics_controller.set_angvel(
+    physics,
+    player_up * player_component.rotation_speed * delta_time * right_input,
+  );
+}
+
+/// Keeps the board bobbing above whatever surface is under it, and works out
+/// whether it's grounded. With nothing under it, or while still rising off a
+/// jump, the board falls towards the last surface it hovered over instead.
+pub fn hover(
+  physics_controller: &PhysicsController,
+  player_component: &mut PlayerMovementComponent,
+  physics: &mut PhysicsComponent,
+  transform: &TransformComponent,
+  running_time: f32,
+  delta_time: f32,
+) {
+  if player_component.grind.is_some() {
+    return;
+  }
+
+  let height_delta =
+    player_component.max_height_from_surface - player_component.min_height_from_surface;
+
+  let player_up = -player_component.down_vector;
+  let was_airborne = !player_component.grounded;
+
+  let ray = Ray::new(transform.translation.into(), -player_up);
+  let toi = 1.00;
+  let solid = true;
+
+  let mut surface = None;
+  if let Some(rigidbody_handle) = physics_controller.get_rigid_body(&physics.joint.body.id) {
+    let filter = QueryFilter::default();
+    let filter = filter.exclude_rigid_body(rigidbody_handle);
+
+    if let Some((_, collider, intersection)) = physics_controller.raycast(&ray, toi, solid, filter)
+    {
+      log::debug!("intersection: {:?}", intersection);
+      surface = Some(intersection.normal);
+    }
+  }
+
+  let rising = player_component.air_velocity > 0.0;
+  player_component.grounded = surface.is_some() && !rising;
+
+  if let Some(normal) = surface
+    && !rising
+  {
+    if was_airborne {
+      land(player_component, transform, &normal);
+    }
+    player_component.down_vector = -normal;
+  }
+
+  let player_up = -player_component.down_vector;
+
+  let old_linvel = physics_controller.linvel(physics);
+
+  if !player_component.grounded {
+    player_component.air_velocity -= player_component.gravity * delta_time;
+    physics_controller.set_linvel(
+      physics,
+      old_linvel + player_up * player_component.air_velocity,
+    );
+    return;
+  }
+  player_component.air_velocity = 0.0;
+
+  physics_controller.set_linvel(
+    physics,
+    old_linvel
+      + player_up
+        * (f32::sin(running_time * player_component.height_from_surface_speed) * height_delta
+          + player_component.min_height_from_surface),
+  );
+}
+
+/// Works out how square to the surface the board came down, and takes speed
+/// off landings that weren't square enough.
+fn land(
+  player_component: &mut PlayerMovementComponent,
+  transform: &TransformComponent,
+  normal: &Vector3<f32>,
+) {
+  let alignment = landing_alignment(transform, normal);
+  player_component.last_landing = alignment;
+
+  let tolerance = player_component.landing_tolerance;
+  if alignment < tolerance {
+    let miss = (tolerance - alignment) / tolerance.max(f32::EPSILON);
+    player_component.current_velocity *= 1.0 - player_component.landing_speed_loss * miss;
+  }
+}
+
+/// How square to the surface the board sits, out of 1: its up axis against
+/// the surface normal, so pitch and roll both count.
+pub fn landing_alignment(transform: &TransformComponent, normal: &Vector3<f32>) -> f32 {
+  let rotation = transform.rotation;
+  let up = Rotation3::from_euler_angles(rotation.x, rotation.y, rotation.z) * Vector3::y();
+  up.dot(normal).max(0.0)
+}
+
+pub fn update_state(
+  player_component: &mut PlayerMovementComponent,
+  input: &PlayerInput,
+  delta_time: f32,
+) {
+  player_component.state_time += delta_time;
+
+  let state = next_state(player_component, input);
+  player_component.set_state(state);
+}
+
+pub fn next_state(player_component: &PlayerMovementComponent, input: &PlayerInput) -> BoardState {
+  let speed = player_component.current_velocity.abs();
+  let steering = input.direction_vector.x;
+  let braking = input.actions.contains(&Actions::Brake);
+
+  if player_component.grind.is_some() {
+    return BoardState::Grinding;
+  }
+
+  if !player_component.grounded {
+    if input.actions.contains(&Actions::Grab) {
+      return BoardState::Grabbing;
+    }
+    return BoardState::Airborne;
+  }
+
+  let landing =
+    player_component.state == BoardState::Landing && player_component.state_time < LANDING_TIME;
+  if player_component.state == BoardState::Airborne || landing {
+    return BoardState::Landing;
+  }
+
+  if speed < MIN_MOVING_SPEED {
+    return BoardState::Idle;
+  }
+
+  if braking {
+    if steering != 0.0 {
+      return BoardState::Drifting;
+    }
+    return BoardState::Braking;
+  }
+
+  if steering > 0.0 {
+    BoardState::LeaningRight
+  } else if steering < 0.0 {
+    BoardState::LeaningLeft
+  } else if speed >= player_component.effective_max_velocity() * MAX_SPEED_RATIO {
+    BoardState::MaxSpeed
+  } else {
+    BoardState::Cruising
+  }
+}
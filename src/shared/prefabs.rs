@@ -0,0 +1,25 @@
+use engine::application::scene::Prefab;
+
+use crate::shared::components::{Role, RoleComponent};
+
+pub fn prefab_component<T: 'static>(prefab: &Prefab) -> Option<&T> {
+  prefab
+    .components
+    .iter()
+    .find_map(|component| component.as_any().downcast_ref::<T>())
+}
+
+pub fn prefab_role(prefab: &Prefab) -> Option<Role> {
+  prefab_component::<RoleComponent>(prefab).map(|role| role.role)
+}
+
+/// Looks a loadout pick up among the prefabs declaring `role`. Unknown or
+/// missing picks fall back to the first one.
+pub fn pick<'a>(prefabs: &'a [Prefab], role: Role, name: Option<&str>) -> Option<&'a Prefab> {
+  let mut candidates = prefabs
+    .iter()
+    .filter(|prefab| prefab_role(prefab) == Some(role));
+  name
+    .and_then(|name| candidates.clone().find(|prefab| prefab.tag.name == name))
+    .or_else(|| candidates.next())
+}
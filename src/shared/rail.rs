@@ -0,0 +1,239 @@
+use engine::application::scene::{Scene, TransformComponent};
+use engine::Entity;
+use nalgebra::{Rotation3, Vector3};
+
+use crate::shared::components::GrindRailComponent;
+
+/// A grind rail's polyline in world space.
+#[derive(Debug, Clone)]
+pub struct Rail {
+  pub entity: Entity,
+  pub snap_distance: f32,
+  pub wobble: f32,
+  pub instability: f32,
+  points: Vec<Vector3<f32>>,
+  /// Along the rail to each point.
+  distances: Vec<f32>,
+}
+
+/// The closest point on a rail to somewhere.
+#[derive(Debug, Clone, Copy)]
+pub struct RailPosition {
+  pub distance: f32,
+  pub point: Vector3<f32>,
+  /// Unit length, towards the rail's last point.
+  pub tangent: Vector3<f32>,
+  /// From the point to where the question was asked from.
+  pub gap: f32,
+}
+
+impl Rail {
+  /// `None` for rails with fewer than two points.
+  pub fn new(
+    entity: Entity,
+    transform: &TransformComponent,
+    rail: &GrindRailComponent,
+  ) -> Option<Self> {
+    if rail.points.len() < 2 {
+      return None;
+    }
+
+    let rotation = transform.rotation;
+    let rotation = Rotation3::from_euler_angles(rotation.x, rotation.y, rotation.z);
+    let points: Vec<Vector3<f32>> = rail
+      .points
+      .iter()
+      .map(|point| transform.translation + rotation * point)
+      .collect();
+
+    let mut distances = vec![0.0];
+    for pair in points.windows(2) {
+      distances.push(distances[distances.len() - 1] + (pair[1] - pair[0]).norm());
+    }
+
+    Some(Self {
+      entity,
+      snap_distance: rail.snap_distance,
+      wobble: rail.wobble,
+      instability: rail.instability,
+      points,
+      distances,
+    })
+  }
+
+  pub fn length(&self) -> f32 {
+    self.distances[self.distances.len() - 1]
+  }
+
+  pub fn closest(&self, position: &Vector3<f32>) -> RailPosition {
+    let mut closest: Option<RailPosition> = None;
+    for (index, pair) in self.points.windows(2).enumerate() {
+      let segment = pair[1] - pair[0];
+      let length = segment.norm();
+      if length < f32::EPSILON {
+        continue;
+      }
+      let tangent = segment / length;
+      let along = (position - pair[0]).dot(&tangent).clamp(0.0, length);
+      let point = pair[0] + tangent * along;
+      let gap = (position - point).norm();
+
+      if closest.is_none_or(|closest| gap < closest.gap) {
+        closest = Some(RailPosition {
+          distance: self.distances[index] + along,
+          point,
+          tangent,
+          gap,
+        });
+      }
+    }
+
+    closest.unwrap_or(RailPosition {
+      distance: 0.0,
+      point: self.points[0],
+      tangent: Vector3::z(),
+      gap: (position - self.points[0]).norm(),
+    })
+  }
+
+  /// The point `distance` along the rail, and which way the rail goes there.
+  pub fn at(&self, distance: f32) -> (Vector3<f32>, Vector3<f32>) {
+    let distance = distance.clamp(0.0, self.length());
+    let index = self
+      .distances
+      .partition_point(|start| *start <= distance)
+      .clamp(1, self.points.len() - 1);
+
+    let (from, to) = (self.points[index - 1], self.points[index]);
+    let tangent = (to - from)
+      .try_normalize(f32::EPSILON)
+      .unwrap_or_else(Vector3::z);
+    (
+      from + tangent * (distance - self.distances[index - 1]),
+      tangent,
+    )
+  }
+}
+
+/// The level's grind rails, gathered once per frame.
+#[derive(Debug, Default)]
+pub struct GrindRails {
+  rails: Vec<Rail>,
+}
+
+impl GrindRails {
+  pub fn gather(scene: &mut Scene) -> Self {
+    let rails = scene
+      .query_mut::<(&GrindRailComponent, &TransformComponent)>()
+      .into_iter()
+      .filter_map(|(entity, (rail, transform))| Rail::new(entity, transform, rail))
+      .collect();
+
+    Self { rails }
+  }
+
+  pub fn get(&self, entity: Entity) -> Option<&Rail> {
+    self.rails.iter().find(|rail| rail.entity == entity)
+  }
+
+  /// The closest rail within snapping distance of `position`, if any.
+  pub fn nearest(&self, position: &Vector3<f32>) -> Option<(&Rail, RailPosition)> {
+    self
+      .rails
+      .iter()
+      .map(|rail| (rail, rail.closest(position)))
+      .filter(|(rail, at)| at.gap <= rail.snap_distance)
+      .min_by(|(_, a), (_, b)| a.gap.total_cmp(&b.gap))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::FRAC_PI_2;
+
+  fn rail(points: &[[f32; 3]], translation: Vector3<f32>, yaw: f32) -> Rail {
+    let transform = TransformComponent {
+      translation,
+      rotation: Vector3::new(0.0, yaw, 0.0),
+      ..Default::default()
+    };
+
+    Rail::new(
+      Entity::DANGLING,
+      &transform,
+      &GrindRailComponent {
+        points: points.iter().map(|point| Vector3::from(*point)).collect(),
+        snap_distance: 1.5,
+        wobble: 0.6,
+        instability: 1.5,
+      },
+    )
+    .unwrap()
+  }
+
+  /// Ten units along x, then ten along z.
+  fn bent() -> Rail {
+    rail(
+      &[[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [10.0, 0.0, 10.0]],
+      Vector3::zeros(),
+      0.0,
+    )
+  }
+
+  #[test]
+  fn closest_finds_the_nearest_segment() {
+    let rail = bent();
+    assert!((rail.length() - 20.0).abs() < 1e-4);
+
+    let first = rail.closest(&Vector3::new(4.0, 1.0, 0.0));
+    assert!((first.distance - 4.0).abs() < 1e-4);
+    assert!((first.point - Vector3::new(4.0, 0.0, 0.0)).norm() < 1e-4);
+    assert!((first.tangent - Vector3::x()).norm() < 1e-4);
+    assert!((first.gap - 1.0).abs() < 1e-4);
+
+    let second = rail.closest(&Vector3::new(12.0, 0.0, 6.0));
+    assert!((second.distance - 16.0).abs() < 1e-4);
+    assert!((second.tangent - Vector3::z()).norm() < 1e-4);
+    assert!((second.gap - 2.0).abs() < 1e-4);
+  }
+
+  #[test]
+  fn closest_stops_at_the_ends() {
+    let rail = bent();
+
+    let before = rail.closest(&Vector3::new(-3.0, 4.0, 0.0));
+    assert!(before.distance.abs() < 1e-4);
+    assert!((before.gap - 5.0).abs() < 1e-4);
+
+    let after = rail.closest(&Vector3::new(10.0, 0.0, 13.0));
+    assert!((after.distance - rail.length()).abs() < 1e-4);
+    assert!((after.gap - 3.0).abs() < 1e-4);
+  }
+
+  #[test]
+  fn closest_agrees_with_at() {
+    let rail = bent();
+    for distance in [0.0, 2.5, 9.0, 11.0, 17.0, 20.0] {
+      let (point, tangent) = rail.at(distance);
+      let closest = rail.closest(&point);
+      assert!((closest.distance - distance).abs() < 1e-4);
+      assert!((closest.tangent - tangent).norm() < 1e-4);
+      assert!(closest.gap < 1e-4);
+    }
+  }
+
+  #[test]
+  fn rails_move_and_turn_with_their_transform() {
+    let rail = rail(
+      &[[0.0, 0.0, 0.0], [0.0, 0.0, 10.0]],
+      Vector3::new(5.0, 2.0, 0.0),
+      FRAC_PI_2,
+    );
+
+    let closest = rail.closest(&Vector3::new(10.0, 2.0, 0.0));
+    assert!((closest.distance - 5.0).abs() < 1e-4);
+    assert!((closest.tangent - Vector3::x()).norm() < 1e-4);
+    assert!(closest.gap < 1e-4);
+  }
+}
@@ -0,0 +1,272 @@
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::shared::loadout::Loadout;
+
+/// Bumped whenever the layout below changes, so old files are rejected
+/// instead of decoding into garbage.
+pub const REPLAY_VERSION: u16 = 2;
+
+/// A recorded race: who took part, where every racer was on every server
+/// tick, and what happened along the way. Racers are referred to by their
+/// index in `racers` to keep frames small.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+  pub version: u16,
+  pub level: String,
+  pub racers: Vec<ReplayRacer>,
+  pub frames: Vec<ReplayFrame>,
+  pub events: Vec<ReplayEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayRacer {
+  pub id: Uuid,
+  pub username: String,
+  pub loadout: Loadout,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayFrame {
+  /// Seconds since the race started.
+  pub time: f32,
+  pub transforms: Vec<ReplayTransform>,
+}
+
+/// Steps per meter translations are stored in, which keeps them exact to 3cm
+/// within a kilometer of the origin.
+const TRANSLATION_STEPS: f32 = 32.0;
+/// Steps per radian euler angles are stored in.
+const ROTATION_STEPS: f32 = i16::MAX as f32 / std::f32::consts::PI;
+
+/// A racer's transform, quantized to 16 bits per axis to keep frames small.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTransform {
+  pub racer: u16,
+  translation: [i16; 3],
+  rotation: [i16; 3],
+}
+
+impl ReplayTransform {
+  pub fn new(racer: u16, translation: &Vector3<f32>, rotation: &Vector3<f32>) -> Self {
+    Self {
+      racer,
+      translation: translation
+        .map(|meters| (meters * TRANSLATION_STEPS).round() as i16)
+        .into(),
+      rotation: rotation
+        .map(|angle| (wrap_angle(angle) * ROTATION_STEPS).round() as i16)
+        .into(),
+    }
+  }
+
+  pub fn translation(&self) -> Vector3<f32> {
+    Vector3::from(self.translation).map(|steps| steps as f32 / TRANSLATION_STEPS)
+  }
+
+  pub fn rotation(&self) -> [f32; 3] {
+    self.rotation.map(|steps| steps as f32 / ROTATION_STEPS)
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayEvent {
+  pub time: f32,
+  pub racer: u16,
+  pub kind: ReplayEventKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReplayEventKind {
+  Checkpoint { checkpoint: u32, lap: u32 },
+  SmokeBomb,
+  Finish { place: u32 },
+}
+
+impl Replay {
+  pub fn new(level: &str) -> Self {
+    Self {
+      version: REPLAY_VERSION,
+      level: level.to_string(),
+      ..Default::default()
+    }
+  }
+
+  pub fn encode(&self) -> Vec<u8> {
+    bincode::serialize(self).expect("replay is always serializable")
+  }
+
+  pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+    let replay: Self = bincode::deserialize(bytes).map_err(|error| error.to_string())?;
+    if replay.version != REPLAY_VERSION {
+      return Err(format!(
+        "replay version {} is not supported, expected {}",
+        replay.version, REPLAY_VERSION
+      ));
+    }
+    Ok(replay)
+  }
+
+  pub fn duration(&self) -> f32 {
+    self.frames.last().map_or(0.0, |frame| frame.time)
+  }
+
+  /// Where each racer was at `time`, interpolated between the two closest
+  /// frames. Racers missing from either frame are left out.
+  pub fn sample(&self, time: f32) -> Vec<(u16, Vector3<f32>, Vector3<f32>)> {
+    let next = self.frames.partition_point(|frame| frame.time <= time);
+    let (Some(from), Some(to)) = (
+      self.frames.get(next.saturating_sub(1)),
+      self
+        .frames
+        .get(next.min(self.frames.len().saturating_sub(1))),
+    ) else {
+      return vec![];
+    };
+
+    let span = to.time - from.time;
+    let t = if span > f32::EPSILON {
+      ((time - from.time) / span).clamp(0.0, 1.0)
+    } else {
+      0.0
+    };
+
+    from
+      .transforms
+      .iter()
+      .filter_map(|start| {
+        let end = to.transforms.iter().find(|end| end.racer == start.racer)?;
+        let translation = start.translation().lerp(&end.translation(), t);
+        let rotation = lerp_euler(start.rotation(), end.rotation(), t);
+        Some((start.racer, translation, rotation))
+      })
+      .collect()
+  }
+
+  /// Events that happened in `(from, to]`, in order.
+  pub fn events_between(&self, from: f32, to: f32) -> impl Iterator<Item = &ReplayEvent> {
+    self
+      .events
+      .iter()
+      .filter(move |event| event.time > from && event.time <= to)
+  }
+}
+
+/// Brings an angle into `[-PI, PI]`.
+fn wrap_angle(angle: f32) -> f32 {
+  use std::f32::consts::PI;
+
+  (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// Blends euler angles along the short way around, so a racer turning past
+/// +-PI doesn't spin all the way back.
+fn lerp_euler(from: [f32; 3], to: [f32; 3], t: f32) -> Vector3<f32> {
+  use std::f32::consts::PI;
+
+  Vector3::from_fn(|axis, _| {
+    let mut delta = (to[axis] - from[axis]) % (2.0 * PI);
+    if delta > PI {
+      delta -= 2.0 * PI;
+    } else if delta < -PI {
+      delta += 2.0 * PI;
+    }
+    from[axis] + delta * t
+  })
+}
+
+/// Playback cursor over a `Replay`. Works the same whether it's driving a
+/// client scene or a headless tool.
+#[derive(Debug, Clone)]
+pub struct ReplayPlayback {
+  pub replay: Replay,
+  pub time: f32,
+  pub speed: f32,
+  pub paused: bool,
+}
+
+impl ReplayPlayback {
+  pub fn new(replay: Replay) -> Self {
+    Self {
+      replay,
+      time: 0.0,
+      speed: 1.0,
+      paused: false,
+    }
+  }
+
+  /// Moves the cursor forward and returns the events it passed over.
+  pub fn advance(&mut self, delta_time: f32) -> Vec<ReplayEvent> {
+    if self.paused {
+      return vec![];
+    }
+
+    let from = self.time;
+    self.time = (self.time + delta_time * self.speed).clamp(0.0, self.replay.duration());
+    self
+      .replay
+      .events_between(from, self.time)
+      .cloned()
+      .collect()
+  }
+
+  pub fn seek(&mut self, time: f32) {
+    self.time = time.clamp(0.0, self.replay.duration());
+  }
+
+  pub fn set_speed(&mut self, speed: f32) {
+    self.speed = speed.max(0.0);
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.time >= self.replay.duration()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::PI;
+
+  #[test]
+  fn transforms_survive_quantizing() {
+    let translation = Vector3::new(312.4, -5.03, -87.71);
+    let rotation = Vector3::new(0.3, -2.9, 3.1);
+    let transform = ReplayTransform::new(3, &translation, &rotation);
+
+    assert_eq!(transform.racer, 3);
+    assert!((transform.translation() - translation).amax() <= 0.5 / TRANSLATION_STEPS);
+    assert!((Vector3::from(transform.rotation()) - rotation).amax() <= 0.5 / ROTATION_STEPS);
+  }
+
+  #[test]
+  fn angles_are_wrapped_before_quantizing() {
+    let transform = ReplayTransform::new(
+      0,
+      &Vector3::zeros(),
+      &Vector3::new(3.0 * PI / 2.0, 0.0, 0.0),
+    );
+    assert!((transform.rotation()[0] + PI / 2.0).abs() < 1e-3);
+  }
+
+  #[test]
+  fn frames_are_interpolated() {
+    let mut replay = Replay::new("arena.lvl");
+    for (time, x) in [(0.0, 0.0), (1.0, 10.0)] {
+      replay.frames.push(ReplayFrame {
+        time,
+        transforms: vec![ReplayTransform::new(
+          0,
+          &Vector3::new(x, 0.0, 0.0),
+          &Vector3::zeros(),
+        )],
+      });
+    }
+
+    let replay = Replay::decode(&replay.encode()).unwrap();
+    let (racer, translation, _) = replay.sample(0.25).pop().unwrap();
+    assert_eq!(racer, 0);
+    assert!((translation.x - 2.5).abs() < 1e-3);
+  }
+}
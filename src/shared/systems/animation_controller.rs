@@ -0,0 +1,50 @@
+#![cfg(target_arch = "wasm32")]
+
+use crate::shared::components::{AnimationControllerComponent, PlayerMovementComponent};
+
+use engine::application::components::AnimationComponent;
+use engine::application::scene::Scene;
+use engine::systems::{Backpack, Initializable, Inventory, System};
+use engine::utils::units::Time;
+
+/// Blends the avatar's animation clips to follow the board state computed by
+/// the `PlayerMovementSystem`. The clip for the current state fades in over its
+/// `blend_time` while every other clip fades out.
+pub struct AnimationControllerSystem {}
+
+impl Initializable for AnimationControllerSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl System for AnimationControllerSystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+
+    for (_, (player_component, controller, animation)) in scene.query_mut::<(
+      &PlayerMovementComponent,
+      &AnimationControllerComponent,
+      &mut AnimationComponent,
+    )>() {
+      let Some(target) = controller.animation_for(player_component.state) else {
+        continue;
+      };
+
+      let step = if target.blend_time > 0.0 {
+        delta_time / target.blend_time
+      } else {
+        1.0
+      };
+
+      for clip in animation.animations.iter_mut() {
+        if clip.id == target.animation {
+          clip.weight = (clip.weight + step).min(1.0);
+          clip.rate = target.rate;
+        } else {
+          clip.weight = (clip.weight - step).max(0.0);
+        }
+      }
+    }
+  }
+}
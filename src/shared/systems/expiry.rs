@@ -0,0 +1,33 @@
+use crate::shared::components::ExpiryComponent;
+
+use engine::application::scene::Scene;
+use engine::systems::{Backpack, Initializable, Inventory, System};
+use engine::utils::units::Time;
+use engine::Entity;
+
+/// Counts down `ExpiryComponent`s and despawns whatever ran out.
+pub struct ExpirySystem {}
+
+impl Initializable for ExpirySystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl System for ExpirySystem {
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let delta_time = **backpack.get::<Time>().unwrap();
+
+    let mut expired: Vec<Entity> = vec![];
+    for (entity, expiry) in scene.query_mut::<&mut ExpiryComponent>() {
+      expiry.remaining -= delta_time;
+      if expiry.remaining <= 0.0 {
+        expired.push(entity);
+      }
+    }
+
+    for entity in expired {
+      let _ = scene.despawn(entity);
+    }
+  }
+}
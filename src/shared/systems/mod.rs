@@ -1 +1,3 @@
+pub mod animation_controller;
+pub mod expiry;
 pub mod player_movement;
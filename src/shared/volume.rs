@@ -0,0 +1,34 @@
+use engine::application::scene::TransformComponent;
+use nalgebra::{Rotation3, Vector3};
+
+/// A box placed and turned by a prefab's transform, for level trigger
+/// volumes.
+#[derive(Debug, Clone)]
+pub struct BoxVolume {
+  pub center: Vector3<f32>,
+  pub rotation: Rotation3<f32>,
+  pub half_extents: Vector3<f32>,
+}
+
+impl BoxVolume {
+  pub fn new(transform: &TransformComponent, half_extents: Vector3<f32>) -> Self {
+    let rotation = transform.rotation;
+    Self {
+      center: transform.translation,
+      rotation: Rotation3::from_euler_angles(rotation.x, rotation.y, rotation.z),
+      half_extents,
+    }
+  }
+
+  pub fn contains(&self, position: &Vector3<f32>) -> bool {
+    let local = self.rotation.inverse() * (position - self.center);
+    local.x.abs() <= self.half_extents.x
+      && local.y.abs() <= self.half_extents.y
+      && local.z.abs() <= self.half_extents.z
+  }
+
+  /// Turns a direction given in the box's own axes into world space.
+  pub fn world_direction(&self, local: &Vector3<f32>) -> Vector3<f32> {
+    self.rotation * local
+  }
+}